@@ -0,0 +1,20 @@
+use super::fields::{build_command, CommandFieldArguments};
+use crate::core::config::{CommandType, Config};
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct CommandAddArguments {
+    path: String,
+    #[command(flatten)]
+    fields: CommandFieldArguments,
+}
+
+pub fn init(args: CommandAddArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new()?;
+    let interactive = !args.fields.is_complete();
+    let command = build_command(None, args.fields, interactive, &config)?;
+
+    config.insert_command(&args.path, CommandType::Command(Box::new(command)))?;
+    config.save()?;
+    Ok(())
+}
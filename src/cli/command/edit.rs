@@ -0,0 +1,43 @@
+use super::fields::{build_command, CommandFieldArguments};
+use crate::core::config::{CommandType, Config};
+use crate::utils::error::CliError;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct CommandEditArguments {
+    path: String,
+    #[command(flatten)]
+    fields: CommandFieldArguments,
+    #[arg(long = "remove-header", value_name = "NAME")]
+    remove_headers: Vec<String>,
+    #[arg(long, conflicts_with = "body")]
+    no_body: bool,
+}
+
+pub fn init(args: CommandEditArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new()?;
+    let mut existing = match config.get_command(&args.path)? {
+        CommandType::Command(command) => (**command).clone(),
+        CommandType::NestedCommand(_) => {
+            return Err(Box::new(CliError {
+                message: format!("`{}` is a group of commands", args.path),
+                help: Some("only individual commands can be edited".to_string()),
+            }))
+        }
+    };
+
+    for name in &args.remove_headers {
+        existing.headers.remove(name);
+    }
+    if args.no_body {
+        existing.body = None;
+    }
+
+    let interactive = args.fields.is_empty() && args.remove_headers.is_empty() && !args.no_body;
+    let command = build_command(Some(existing), args.fields, interactive, &config)?;
+
+    config.remove_command(&args.path)?;
+    config.insert_command(&args.path, CommandType::Command(Box::new(command)))?;
+    config.save()?;
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use crate::core::command::{BodyType, Command};
+use crate::core::config::Config;
+use crate::utils::error::CliError;
+use crate::utils::headers::Headers;
+use crate::utils::http::HttpMethod;
+use crate::utils::input::CustomAutocomplete;
+use clap::Args;
+use edit::edit;
+use indexmap::IndexMap;
+use inquire::{Confirm, InquireError, Select, Text};
+use serde_json::Value;
+use std::error::Error;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+#[derive(Args, Debug)]
+pub struct CommandFieldArguments {
+    #[arg(long, value_parser = HttpMethod::from_str)]
+    method: Option<HttpMethod>,
+    #[arg(long)]
+    url: Option<String>,
+    #[arg(long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+    /// JSON body, or the text or file path for `text` and `binary` bodies
+    #[arg(long, value_name = "JSON")]
+    body: Option<String>,
+    #[arg(long, value_enum)]
+    body_type: Option<BodyType>,
+    /// GraphQL query document, or `@path` of a `.graphql` file
+    #[arg(long, value_name = "QUERY")]
+    graphql: Option<String>,
+}
+
+impl CommandFieldArguments {
+    pub fn is_empty(&self) -> bool {
+        self.method.is_none()
+            && self.url.is_none()
+            && self.headers.is_empty()
+            && self.body.is_none()
+            && self.body_type.is_none()
+            && self.graphql.is_none()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.method.is_some() && self.url.is_some()
+    }
+}
+
+fn parse_header(input: &str) -> Result<(String, String), String> {
+    match input.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!(
+            "invalid header `{}`, expected `Name: value`",
+            input
+        )),
+    }
+}
+
+fn parse_body(input: &str, body_type: &BodyType) -> Result<Value, CliError> {
+    if matches!(body_type, BodyType::Text | BodyType::Binary) {
+        return Ok(Value::String(input.to_string()));
+    }
+    serde_json::from_str(input).map_err(|e| CliError {
+        message: format!("request body is not valid JSON: {}", e),
+        help: None,
+    })
+}
+
+fn prompt_error(error: InquireError) -> CliError {
+    CliError {
+        message: error.to_string(),
+        help: None,
+    }
+}
+
+fn prompt_method(current: &HttpMethod) -> Result<HttpMethod, CliError> {
+    let methods: Vec<HttpMethod> = HttpMethod::iter().collect();
+    let cursor = methods.iter().position(|m| m == current).unwrap_or(0);
+    Select::new("Method:", methods)
+        .with_starting_cursor(cursor)
+        .prompt()
+        .map_err(prompt_error)
+}
+
+fn prompt_url(current: &str, config: &Config) -> Result<String, CliError> {
+    let mut suggestions: Vec<String> = config
+        .envs
+        .values()
+        .flat_map(|vars| vars.keys())
+        .map(|var| format!("{{{{{}}}}}", var))
+        .collect();
+    suggestions.sort();
+    suggestions.dedup();
+
+    Text::new("URL:")
+        .with_initial_value(current)
+        .with_autocomplete(CustomAutocomplete::new(suggestions))
+        .with_validator(inquire::required!("URL is required"))
+        .prompt()
+        .map_err(prompt_error)
+}
+
+fn prompt_headers(current: &Headers) -> Result<Headers, CliError> {
+    let mut headers = Headers::new();
+
+    let mut current: Vec<(&str, &str)> = current.iter().collect();
+    current.sort_by_key(|(name, _)| *name);
+    for (name, value) in current {
+        let input = Text::new("Header (clear to remove):")
+            .with_initial_value(&format!("{}: {}", name, value))
+            .prompt()
+            .map_err(prompt_error)?;
+        if let Ok((name, value)) = parse_header(&input) {
+            headers.append(name, value);
+        }
+    }
+
+    loop {
+        let input = Text::new("Add header (Name: value), leave empty to finish:")
+            .prompt()
+            .map_err(prompt_error)?;
+        if input.trim().is_empty() {
+            break;
+        }
+        match parse_header(&input) {
+            Ok((name, value)) => headers.append(name, value),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    Ok(headers)
+}
+
+fn prompt_body(
+    current: Option<Value>,
+    body_type: &BodyType,
+) -> Result<Option<Value>, Box<dyn Error>> {
+    let message = match current {
+        Some(_) => "Edit the request body?",
+        None => "Add a request body?",
+    };
+    if !Confirm::new(message)
+        .with_default(false)
+        .prompt()
+        .map_err(prompt_error)?
+    {
+        return Ok(current);
+    }
+
+    let initial = match (current, body_type) {
+        (Some(Value::String(body)), BodyType::Text | BodyType::Binary) => body,
+        (Some(body), _) => serde_json::to_string_pretty(&body)?,
+        (None, BodyType::Text | BodyType::Binary) => String::new(),
+        (None, _) => "{\n}".to_string(),
+    };
+    let input = edit(initial)?;
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(parse_body(input.trim_end_matches('\n'), body_type)?))
+}
+
+/// Applies the provided fields on top of `existing`. In interactive mode, every
+/// field not provided as a flag is prompted for.
+pub fn build_command(
+    existing: Option<Command>,
+    fields: CommandFieldArguments,
+    interactive: bool,
+    config: &Config,
+) -> Result<Command, Box<dyn Error>> {
+    let mut command = existing.unwrap_or_else(|| Command {
+        method: HttpMethod::GET,
+        url: String::new(),
+        headers: Headers::new(),
+        body_type: BodyType::Json,
+        body: None,
+        graphql: None,
+        grpc: None,
+        tls: None,
+        cookies: None,
+        stream: false,
+        postscript: None,
+        extra: IndexMap::new(),
+    });
+
+    if let Some(method) = fields.method {
+        command.method = method;
+    } else if interactive {
+        command.method = prompt_method(&command.method)?;
+    }
+
+    if let Some(url) = fields.url {
+        command.url = url;
+    } else if interactive {
+        command.url = prompt_url(&command.url, config)?;
+    }
+
+    command.headers.merge(fields.headers.into_iter().collect());
+    if interactive {
+        command.headers = prompt_headers(&command.headers)?;
+    }
+
+    if let Some(body_type) = fields.body_type {
+        command.body_type = body_type;
+    }
+
+    if let Some(graphql) = fields.graphql {
+        command.graphql = Some(graphql);
+    }
+
+    if let Some(body) = fields.body {
+        command.body = Some(parse_body(&body, &command.body_type)?);
+    } else if interactive {
+        command.body = prompt_body(command.body, &command.body_type)?;
+    }
+
+    Ok(command)
+}
@@ -0,0 +1,35 @@
+use crate::core::config::{CommandType, Config};
+use colored::Colorize;
+use indexmap::IndexMap;
+
+fn print_tree(commands: &IndexMap<String, Box<CommandType>>, prefix: &str) {
+    let mut names: Vec<&String> = commands.keys().collect();
+    names.sort();
+
+    for (index, name) in names.iter().enumerate() {
+        let is_last = index + 1 == names.len();
+        let branch = if is_last { "└── " } else { "├── " };
+
+        match *commands[*name] {
+            CommandType::Command(ref command) => println!(
+                "{}{}{} {} {}",
+                prefix,
+                branch,
+                name,
+                command.method.to_string().green(),
+                command.url
+            ),
+            CommandType::NestedCommand(ref nested_commands) => {
+                println!("{}{}{}", prefix, branch, name.bold());
+                let indent = if is_last { "    " } else { "│   " };
+                print_tree(nested_commands, &format!("{}{}", prefix, indent));
+            }
+        }
+    }
+}
+
+pub fn init() -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::new()?;
+    print_tree(&config.commands, "");
+    Ok(())
+}
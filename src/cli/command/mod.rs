@@ -0,0 +1,31 @@
+mod add;
+mod edit;
+mod fields;
+mod list;
+mod mv;
+mod rm;
+mod show;
+
+use clap::Subcommand;
+use std::error::Error;
+
+#[derive(Subcommand, Debug)]
+pub enum CommandCommand {
+    Add(add::CommandAddArguments),
+    Edit(edit::CommandEditArguments),
+    Rm(rm::CommandRmArguments),
+    Mv(mv::CommandMvArguments),
+    List,
+    Show(show::CommandShowArguments),
+}
+
+pub fn init(command: CommandCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        CommandCommand::Add(args) => add::init(args),
+        CommandCommand::Edit(args) => edit::init(args),
+        CommandCommand::Rm(args) => rm::init(args),
+        CommandCommand::Mv(args) => mv::init(args),
+        CommandCommand::List => list::init(),
+        CommandCommand::Show(args) => show::init(args),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::core::config::Config;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct CommandMvArguments {
+    from: String,
+    to: String,
+}
+
+pub fn init(args: CommandMvArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new()?;
+    let command = config.remove_command(&args.from)?;
+    config.insert_command(&args.to, command)?;
+    config.save()?;
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use crate::core::config::Config;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct CommandRmArguments {
+    path: String,
+}
+
+pub fn init(args: CommandRmArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new()?;
+    config.remove_command(&args.path)?;
+    config.save()?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use crate::core::config::Config;
+use clap::Args;
+use std::io::{stdout, Write};
+
+#[derive(Args, Debug)]
+pub struct CommandShowArguments {
+    path: String,
+}
+
+pub fn init(args: CommandShowArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::new()?;
+    let command = serde_json::to_value(config.get_command(&args.path)?)?;
+
+    let mut out = stdout();
+    colored_json::write_colored_json(&command, &mut out)?;
+    writeln!(out)?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::core::config::{get_config_file_path, Config};
+use crate::utils::error::CliError;
+use crate::utils::jsonc;
+use clap::Args;
+use jsonc_parser::{parse_to_serde_value, ParseOptions};
+use serde_json::Value;
+use std::fs;
+
+#[derive(Args, Debug)]
+pub struct ConfigFmtArguments {
+    /// Fail if the config is not formatted instead of formatting it
+    #[arg(long)]
+    check: bool,
+}
+
+pub fn init(args: ConfigFmtArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::new()?;
+    let file_path = get_config_file_path();
+    let text = fs::read_to_string(&file_path)?;
+    let formatted = if jsonc::has_comments(&text)? {
+        // NOTE: formatting through the CST keeps the comments
+        let old: Value = parse_to_serde_value(&text, &ParseOptions::default())?;
+        let new = serde_json::to_value(&config)?;
+        jsonc::format(&jsonc::update(&text, &old, &new)?)?
+    } else {
+        config.to_formatted_string()
+    };
+    if text == formatted {
+        return Ok(());
+    }
+
+    if args.check {
+        return Err(Box::new(CliError {
+            message: format!("{} is not formatted", file_path.display()),
+            help: Some("run `hit config fmt` to format it".to_string()),
+        }));
+    }
+    fs::write(&file_path, formatted)?;
+    println!("formatted {}", file_path.display());
+    Ok(())
+}
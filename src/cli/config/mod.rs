@@ -0,0 +1,18 @@
+mod fmt;
+mod schema;
+
+use clap::Subcommand;
+use std::error::Error;
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    Schema,
+    Fmt(fmt::ConfigFmtArguments),
+}
+
+pub fn init(command: ConfigCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        ConfigCommand::Schema => schema::init(),
+        ConfigCommand::Fmt(args) => fmt::init(args),
+    }
+}
@@ -0,0 +1,6 @@
+use crate::core::config::get_config_schema;
+
+pub fn init() -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(&get_config_schema())?);
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use crate::core::cookies::clear_cookies;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct CookiesClearArguments {
+    /// Only clear the cookies of this env
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: CookiesClearArguments) -> Result<(), Box<dyn std::error::Error>> {
+    clear_cookies(args.env.as_deref());
+    Ok(())
+}
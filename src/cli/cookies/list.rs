@@ -0,0 +1,52 @@
+use crate::core::cookies::list_cookies;
+use crate::core::ephenv::now;
+use crate::utils::secret::MASK;
+use clap::Args;
+use colored::Colorize;
+use cookie_store::CookieExpiration;
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct CookiesListArguments {
+    /// Only list the cookies of this env
+    #[arg(long)]
+    env: Option<String>,
+    /// Print values instead of masking them
+    #[arg(long)]
+    reveal: bool,
+}
+
+pub fn init(args: CookiesListArguments) -> Result<(), Box<dyn std::error::Error>> {
+    for (env, cookies) in list_cookies(args.env.as_deref()) {
+        for cookie in cookies {
+            let value = if args.reveal {
+                cookie.value().to_string()
+            } else {
+                MASK.to_string()
+            };
+
+            let mut annotations = vec![format!("[env: {}]", env)];
+            if let Some(domain) = cookie.domain.as_cow() {
+                annotations.push(format!("[domain: {}]", domain));
+            }
+            if &*cookie.path != "/" {
+                annotations.push(format!("[path: {}]", &*cookie.path));
+            }
+            if let CookieExpiration::AtUtc(expires) = cookie.expires {
+                let expires_in = (expires.unix_timestamp() as u64).saturating_sub(now());
+                annotations.push(format!(
+                    "[expires in {}]",
+                    humantime::format_duration(Duration::from_secs(expires_in))
+                ));
+            }
+
+            println!(
+                "{}={} {}",
+                cookie.name(),
+                value,
+                annotations.join(" ").dimmed()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+mod clear;
+mod list;
+
+use clap::Subcommand;
+use std::error::Error;
+
+#[derive(Subcommand, Debug)]
+pub enum CookiesCommand {
+    List(list::CookiesListArguments),
+    Clear(clear::CookiesClearArguments),
+}
+
+pub fn init(command: CookiesCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        CookiesCommand::List(args) => list::init(args),
+        CookiesCommand::Clear(args) => clear::init(args),
+    }
+}
@@ -0,0 +1,11 @@
+use crate::core::env::add_env;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EnvAddArguments {
+    env: String,
+}
+
+pub fn init(args: EnvAddArguments) -> Result<(), Box<dyn std::error::Error>> {
+    add_env(args.env)
+}
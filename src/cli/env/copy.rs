@@ -0,0 +1,12 @@
+use crate::core::env::copy_env;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EnvCopyArguments {
+    from: String,
+    to: String,
+}
+
+pub fn init(args: EnvCopyArguments) -> Result<(), Box<dyn std::error::Error>> {
+    copy_env(&args.from, args.to)
+}
@@ -0,0 +1,45 @@
+use crate::core::env::{get_env_vars, EnvValue};
+use crate::utils::secret::{is_secret_name, MASK};
+use clap::Args;
+use colored::Colorize;
+
+#[derive(Args, Debug)]
+pub struct EnvDiffArguments {
+    env: String,
+    other_env: String,
+    /// Print secret values instead of masking them
+    #[arg(long)]
+    reveal: bool,
+}
+
+pub fn init(args: EnvDiffArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let env_vars = get_env_vars(&args.env)?;
+    let other_env_vars = get_env_vars(&args.other_env)?;
+
+    let mut names: Vec<&String> = env_vars.keys().chain(other_env_vars.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let display = |name: &str, value: &EnvValue| match value {
+        EnvValue::Value(_) if !args.reveal && is_secret_name(name) => MASK.to_string(),
+        EnvValue::Value(value) => value.clone(),
+        reference => format!("({})", reference.source().unwrap()),
+    };
+
+    for name in names {
+        match (env_vars.get(name), other_env_vars.get(name)) {
+            (Some(value), Some(other_value)) if value != other_value => println!(
+                "{} {}: {} → {}",
+                "~".yellow(),
+                name,
+                display(name, value),
+                display(name, other_value)
+            ),
+            (Some(_), None) => println!("{} {}: missing in {}", "-".red(), name, args.other_env),
+            (None, Some(_)) => println!("{} {}: missing in {}", "+".green(), name, args.env),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -2,7 +2,7 @@ use crate::core::env::{get_env, list_envs};
 use colored::Colorize;
 
 pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-    let envs = list_envs();
+    let envs = list_envs()?;
     let current_env = get_env();
 
     for env in envs {
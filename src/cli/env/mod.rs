@@ -1,4 +1,12 @@
+mod add;
+mod copy;
+mod diff;
 mod list;
+mod rename;
+mod rm;
+mod set;
+mod show;
+mod unset;
 mod r#use;
 
 use clap::Subcommand;
@@ -8,11 +16,27 @@ use std::error::Error;
 pub enum EnvCommand {
     Use(r#use::EnvUseArguments),
     List,
+    Add(add::EnvAddArguments),
+    Rm(rm::EnvRmArguments),
+    Copy(copy::EnvCopyArguments),
+    Rename(rename::EnvRenameArguments),
+    Show(show::EnvShowArguments),
+    Diff(diff::EnvDiffArguments),
+    Set(set::EnvSetArguments),
+    Unset(unset::EnvUnsetArguments),
 }
 
 pub fn init(command: EnvCommand) -> Result<(), Box<dyn Error>> {
     match command {
         EnvCommand::Use(args) => r#use::init(args),
         EnvCommand::List => list::init(),
+        EnvCommand::Add(args) => add::init(args),
+        EnvCommand::Rm(args) => rm::init(args),
+        EnvCommand::Copy(args) => copy::init(args),
+        EnvCommand::Rename(args) => rename::init(args),
+        EnvCommand::Show(args) => show::init(args),
+        EnvCommand::Diff(args) => diff::init(args),
+        EnvCommand::Set(args) => set::init(args),
+        EnvCommand::Unset(args) => unset::init(args),
     }
 }
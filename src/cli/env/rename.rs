@@ -0,0 +1,12 @@
+use crate::core::env::rename_env;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EnvRenameArguments {
+    from: String,
+    to: String,
+}
+
+pub fn init(args: EnvRenameArguments) -> Result<(), Box<dyn std::error::Error>> {
+    rename_env(&args.from, args.to)
+}
@@ -0,0 +1,11 @@
+use crate::core::env::remove_env;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EnvRmArguments {
+    env: String,
+}
+
+pub fn init(args: EnvRmArguments) -> Result<(), Box<dyn std::error::Error>> {
+    remove_env(&args.env)
+}
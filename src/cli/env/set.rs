@@ -0,0 +1,16 @@
+use crate::core::env::{resolve_env, set_env_var};
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EnvSetArguments {
+    key: String,
+    value: String,
+    /// Env to modify, defaults to the active env
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: EnvSetArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let env = resolve_env(args.env)?;
+    set_env_var(&env, args.key, args.value)
+}
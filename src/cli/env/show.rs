@@ -0,0 +1,49 @@
+use crate::core::env::{get_env_vars, load_dotenv_vars, resolve_env, EnvValue};
+use crate::core::ephenv::get_ephenvs;
+use crate::utils::secret::{is_secret_name, MASK};
+use clap::Args;
+use colored::Colorize;
+
+#[derive(Args, Debug)]
+pub struct EnvShowArguments {
+    /// Env to show, defaults to the active env
+    env: Option<String>,
+    /// Print secret values instead of masking them
+    #[arg(long)]
+    reveal: bool,
+}
+
+pub fn init(args: EnvShowArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let env = resolve_env(args.env)?;
+    let env_vars = get_env_vars(&env)?;
+    let ephenvs = get_ephenvs(Some(&env));
+    let dotenv_vars = if args.reveal {
+        load_dotenv_vars()?
+    } else {
+        Default::default()
+    };
+
+    let mut names: Vec<&String> = env_vars.keys().chain(ephenvs.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        // ephenvs take precedence over env variables when running commands
+        let (value, source) = match ephenvs.get(name) {
+            Some(value) => (EnvValue::Value(value.clone()), Some("ephenv".to_string())),
+            None => (env_vars[name].clone(), env_vars[name].source()),
+        };
+        let value = if !args.reveal && (value.is_reference() || is_secret_name(name)) {
+            MASK.to_string()
+        } else {
+            value.resolve(name, &dotenv_vars)?
+        };
+
+        match source {
+            Some(source) => println!("{}={} {}", name, value, format!("({})", source).dimmed()),
+            None => println!("{}={}", name, value),
+        }
+    }
+
+    Ok(())
+}
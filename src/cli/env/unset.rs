@@ -0,0 +1,15 @@
+use crate::core::env::{resolve_env, unset_env_var};
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EnvUnsetArguments {
+    key: String,
+    /// Env to modify, defaults to the active env
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: EnvUnsetArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let env = resolve_env(args.env)?;
+    unset_env_var(&env, &args.key)
+}
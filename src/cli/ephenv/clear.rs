@@ -0,0 +1,14 @@
+use crate::core::ephenv::clear_ephenvs;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EphenvClearArguments {
+    /// Only clear the values scoped to this env
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: EphenvClearArguments) -> Result<(), Box<dyn std::error::Error>> {
+    clear_ephenvs(args.env.as_deref());
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use crate::core::env::get_env;
+use crate::core::ephenv::get_ephenvs;
+use crate::utils::error::CliError;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EphenvGetArguments {
+    key: String,
+    /// Env to look the value up for, defaults to the active env
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: EphenvGetArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let env = args.env.or_else(get_env);
+    match get_ephenvs(env.as_deref()).get(&args.key) {
+        Some(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        None => Err(Box::new(CliError {
+            message: format!("ephenv `{}` not set", args.key),
+            help: None,
+        })),
+    }
+}
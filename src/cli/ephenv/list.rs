@@ -0,0 +1,41 @@
+use crate::core::ephenv::list_ephenvs;
+use crate::utils::secret::MASK;
+use clap::Args;
+use colored::Colorize;
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct EphenvListArguments {
+    /// Print values instead of masking them
+    #[arg(long)]
+    reveal: bool,
+}
+
+pub fn init(args: EphenvListArguments) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in list_ephenvs() {
+        let value = if args.reveal {
+            entry.ephenv.value().clone()
+        } else {
+            MASK.to_string()
+        };
+
+        let mut annotations = Vec::new();
+        if let Some(env) = entry.env {
+            annotations.push(format!("[env: {}]", env));
+        }
+        if let Some(expires_in) = entry.ephenv.expires_in() {
+            let expires_in = Duration::from_secs(expires_in.as_secs());
+            annotations.push(format!(
+                "[expires in {}]",
+                humantime::format_duration(expires_in)
+            ));
+        }
+
+        if annotations.is_empty() {
+            println!("{}={}", entry.key, value);
+        } else {
+            println!("{}={} {}", entry.key, value, annotations.join(" ").dimmed());
+        }
+    }
+    Ok(())
+}
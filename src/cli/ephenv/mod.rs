@@ -1,4 +1,8 @@
+mod clear;
+mod get;
+mod list;
 mod set;
+mod unset;
 
 use clap::Subcommand;
 use std::error::Error;
@@ -6,10 +10,18 @@ use std::error::Error;
 #[derive(Subcommand, Debug)]
 pub enum EphenvCommand {
     Set(set::EphenvSetArguments),
+    Get(get::EphenvGetArguments),
+    List(list::EphenvListArguments),
+    Unset(unset::EphenvUnsetArguments),
+    Clear(clear::EphenvClearArguments),
 }
 
 pub fn init(command: EphenvCommand) -> Result<(), Box<dyn Error>> {
     match command {
         EphenvCommand::Set(args) => set::init(args),
+        EphenvCommand::Get(args) => get::init(args),
+        EphenvCommand::List(args) => list::init(args),
+        EphenvCommand::Unset(args) => unset::init(args),
+        EphenvCommand::Clear(args) => clear::init(args),
     }
 }
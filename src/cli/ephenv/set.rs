@@ -1,12 +1,20 @@
 use crate::core::ephenv::set_ephenv;
 use clap::Args;
+use std::time::Duration;
 
 #[derive(Args, Debug)]
 pub struct EphenvSetArguments {
     key: String,
     value: String,
+    /// Only use the value when running commands against this env
+    #[arg(long)]
+    env: Option<String>,
+    /// Expire the value after the given duration, e.g. 30m or 12h
+    #[arg(long, value_parser = humantime::parse_duration)]
+    ttl: Option<Duration>,
 }
 
 pub fn init(args: EphenvSetArguments) -> Result<(), Box<dyn std::error::Error>> {
-    Ok(set_ephenv(args.key, args.value))
+    set_ephenv(args.key, args.value, args.env, args.ttl);
+    Ok(())
 }
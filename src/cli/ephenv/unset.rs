@@ -0,0 +1,15 @@
+use crate::core::ephenv::unset_ephenv;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct EphenvUnsetArguments {
+    key: String,
+    /// Unset the value scoped to this env instead of the unscoped one
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: EphenvUnsetArguments) -> Result<(), Box<dyn std::error::Error>> {
+    unset_ephenv(&args.key, args.env.as_deref())?;
+    Ok(())
+}
@@ -1,22 +1,32 @@
-use crate::core::openapi::generate_config;
+use crate::core::config::{get_config_file_path, Config};
+use crate::core::graphql;
+use crate::core::openapi;
+use crate::core::project::init_project;
 use clap::{Args, ValueHint};
-use openapiv3::OpenAPI;
-use std::fs;
 
 #[derive(Args, Debug)]
 pub struct ImportArguments {
     #[arg(value_hint = ValueHint::FilePath)]
     file: String,
+    /// Import a GraphQL schema, as SDL or the result of an introspection
+    /// query, instead of an OpenAPI spec
+    #[arg(long)]
+    graphql: bool,
 }
 
 pub fn init(args: ImportArguments) -> Result<(), Box<dyn std::error::Error>> {
-    // Read the OpenAPI spec from a file
-    let spec_content = fs::read_to_string(args.file)?;
-    let spec: OpenAPI = serde_yaml::from_str(&spec_content)?;
+    let imported = match args.graphql {
+        true => graphql::generate_config_from_file(&args.file)?,
+        false => openapi::generate_config_from_file(&args.file)?,
+    };
 
-    // Generate configuration
-    let config = generate_config(&spec)?;
-
-    config.save().expect("could not create config file");
+    // Merge into the current project, or create one if there is none
+    if get_config_file_path().exists() {
+        let mut config = Config::new()?;
+        config.merge(imported);
+        config.save()?;
+    } else {
+        init_project(&imported)?;
+    }
     Ok(())
 }
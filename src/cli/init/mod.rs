@@ -0,0 +1,56 @@
+use crate::core::config::Config;
+use crate::core::openapi::generate_config_from_file;
+use crate::core::project::init_project;
+use clap::{Args, ValueEnum, ValueHint};
+use std::fs;
+use std::path::Path;
+
+const GITIGNORE_ENTRIES: [&str; 2] = [".hit/.env", ".hit/.env.local"];
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum Template {
+    #[default]
+    Blank,
+    Example,
+}
+
+#[derive(Args, Debug)]
+pub struct InitArguments {
+    /// The config to start the project with
+    #[arg(long, value_enum, default_value_t, conflicts_with = "openapi")]
+    template: Template,
+    /// Generate the config from an OpenAPI spec
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    openapi: Option<String>,
+}
+
+fn get_missing_gitignore_entries(dir: &Path) -> Vec<&'static str> {
+    let gitignore = fs::read_to_string(dir.join(".gitignore")).unwrap_or_default();
+    let lines: Vec<&str> = gitignore.lines().map(str::trim).collect();
+    GITIGNORE_ENTRIES
+        .into_iter()
+        .filter(|entry| !lines.contains(entry))
+        .collect()
+}
+
+pub fn init(args: InitArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let config = match args.openapi {
+        Some(file) => generate_config_from_file(&file)?,
+        None => match args.template {
+            Template::Blank => Config::empty(),
+            Template::Example => Config::example(),
+        },
+    };
+
+    let dir = init_project(&config)?;
+    println!("Initialized hit project in {}", dir.display());
+
+    let missing_entries = get_missing_gitignore_entries(&dir);
+    if !missing_entries.is_empty() {
+        println!("\nConsider adding the following to .gitignore to keep local secrets out of version control:");
+        for entry in missing_entries {
+            println!("  {}", entry);
+        }
+    }
+    Ok(())
+}
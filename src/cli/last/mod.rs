@@ -5,11 +5,11 @@ use std::error::Error;
 
 #[derive(Subcommand, Debug)]
 pub enum LastCommand {
-    View,
+    View(view::ViewArguments),
 }
 
 pub fn init(command: LastCommand) -> Result<(), Box<dyn Error>> {
     match command {
-        LastCommand::View => view::init(),
+        LastCommand::View(args) => view::init(args),
     }
 }
@@ -1,6 +1,10 @@
-use crate::core::app_config::get_app_config;
+use crate::cli::state::format_size;
+use crate::core::app_config::{get_app_config, OmittedBody, MAX_STORED_BODY_SIZE};
+use crate::core::env::get_env;
+use crate::utils::error::CliError;
 use crate::utils::input::CustomAutocomplete;
 use arboard::Clipboard;
+use clap::Args;
 use colored_json;
 use crossterm::event::{read, Event, KeyCode};
 use crossterm::terminal;
@@ -14,13 +18,55 @@ fn get_json_value_from_path<'a, 'b>(json: &'a Value, path: &'b str) -> Option<&'
     json.pointer(format!("/{}", path.replace(".", "/")).as_str())
 }
 
-pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-    let prev_request = get_app_config()
-        .get_prev_request()
-        .expect("No last request found")
-        .clone();
+#[derive(Args, Debug)]
+pub struct ViewArguments {
+    /// Show the last response received in this env instead of the current one
+    #[arg(long)]
+    env: Option<String>,
+}
+
+pub fn init(args: ViewArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let env = args.env.or_else(get_env);
+    let prev_request = match get_app_config().get_prev_request(env.as_deref()) {
+        Some(prev_request) => prev_request.clone(),
+        None => {
+            return Err(Box::new(CliError {
+                message: match env {
+                    Some(env) => format!("no last request found in env `{}`", env),
+                    None => "no last request found".to_string(),
+                },
+                help: None,
+            }))
+        }
+    };
 
-    let mut prev_request = serde_json::to_value(prev_request).unwrap();
+    match &prev_request.omitted_body {
+        Some(OmittedBody::Binary { content_type, size }) => eprintln!(
+            "the binary response body ({}, {}) was not stored",
+            content_type.as_deref().unwrap_or("unknown content type"),
+            format_size(*size)
+        ),
+        Some(OmittedBody::TooLarge { size }) => eprintln!(
+            "the response body ({}) was not stored, as it is larger than {}",
+            format_size(*size),
+            format_size(MAX_STORED_BODY_SIZE as u64)
+        ),
+        Some(OmittedBody::SavedToFile { path, size }) => eprintln!(
+            "the response body ({}) was saved to {}",
+            format_size(*size),
+            path
+        ),
+        Some(OmittedBody::Streamed { events }) => eprintln!(
+            "the response was streamed, and its {} event(s) were not stored",
+            events
+        ),
+        Some(OmittedBody::WebSocket { messages }) => eprintln!(
+            "the request opened a websocket, and its {} message(s) were not stored",
+            messages
+        ),
+        None => {}
+    }
+    let mut prev_request = serde_json::to_value(prev_request.to_response()).unwrap();
     if let Ok(body_json) = serde_json::from_str::<Value>(&prev_request["body"].as_str().unwrap()) {
         prev_request["body"] = body_json;
     }
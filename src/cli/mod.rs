@@ -1,48 +1,87 @@
+mod command;
+mod config;
+mod cookies;
 mod env;
 mod ephenv;
 mod import;
+mod init;
 mod last;
 mod run;
+mod state;
 
 use crate::core::command::Command as ConfigCommand;
 use crate::core::config::{CommandType as ConfigCommandType, Config};
+use crate::core::project::{set_project_dir, PROJECT_DIR_ENV_VAR};
 use crate::utils::error::CliError;
-use clap::{command, Arg, ArgMatches, Command, FromArgMatches as _, Parser, Subcommand};
+use clap::{Arg, ArgMatches, Args as _, Command, FromArgMatches as _, Parser, Subcommand};
 use clap_complete::CompleteEnv;
 use convert_case::{Case, Casing};
+use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 #[derive(Debug, Parser)]
 #[command(version)]
 enum StaticCommand {
+    #[command(subcommand)]
+    Command(command::CommandCommand),
+    #[command(subcommand)]
+    Config(config::ConfigCommand),
     #[command(subcommand)]
     Env(env::EnvCommand),
     #[command(subcommand)]
+    Cookies(cookies::CookiesCommand),
+    #[command(subcommand)]
     Ephenv(ephenv::EphenvCommand),
     #[command(subcommand)]
     Last(last::LastCommand),
     Import(import::ImportArguments),
+    Init(init::InitArguments),
+    #[command(subcommand)]
+    State(state::StateCommand),
 }
 
 fn formulate_command(
     mut command: Command,
-    config_commands: &HashMap<String, Box<ConfigCommandType>>,
+    config_commands: &IndexMap<String, Box<ConfigCommandType>>,
 ) -> Command {
     for (key, value) in config_commands.iter() {
         let subcommand = match **value {
             ConfigCommandType::Command(ref config_command) => {
                 let params = config_command.params();
+                let variables = config_command.graphql_variables();
 
-                let mut subcommand = Command::new(key).arg_required_else_help(!params.is_empty());
-                for param in params {
+                let mut subcommand = Command::new(key)
+                    .arg_required_else_help(!params.is_empty() || !variables.is_empty());
+                for param in &params {
                     subcommand = subcommand.arg(
                         Arg::new(param.to_string())
-                            .long(&param.to_string().to_case(Case::Kebab))
+                            .long(param.to_case(Case::Kebab))
                             .value_name(param.to_string())
                             .help(format!("Provide value for the param :{}", param)),
                     )
                 }
+                // NOTE: variables set in the body of the command are optional
+                let body_variables = match &config_command.body {
+                    Some(serde_json::Value::Object(body)) => body.keys().cloned().collect(),
+                    _ => Vec::new(),
+                };
+                for variable in variables
+                    .into_iter()
+                    .filter(|variable| !params.contains(&variable.name))
+                {
+                    subcommand = subcommand.arg(
+                        Arg::new(variable.name.clone())
+                            .long(variable.name.to_case(Case::Kebab))
+                            .value_name(variable.type_name.clone())
+                            .required(variable.required && !body_variables.contains(&variable.name))
+                            .help(format!(
+                                "Provide value for the GraphQL variable ${}",
+                                variable.name
+                            )),
+                    )
+                }
 
                 subcommand
             }
@@ -58,7 +97,7 @@ fn formulate_command(
 
 fn obtain_run_command_from_matches(
     matches: &ArgMatches,
-    config_commands: &HashMap<String, Box<ConfigCommandType>>,
+    config_commands: &IndexMap<String, Box<ConfigCommandType>>,
     args_map: &mut HashMap<String, String>,
 ) -> ConfigCommand {
     let subcommand_name = matches.subcommand_name().unwrap();
@@ -68,15 +107,11 @@ fn obtain_run_command_from_matches(
     match **config_command_value {
         ConfigCommandType::Command(ref config_command) => {
             for arg_id in subcommand_matches.ids() {
-                args_map.insert(
-                    arg_id.to_string(),
-                    subcommand_matches
-                        .get_one::<String>(arg_id.as_str())
-                        .unwrap()
-                        .to_string(),
-                );
+                if let Some(value) = subcommand_matches.get_one::<String>(arg_id.as_str()) {
+                    args_map.insert(arg_id.to_string(), value.to_string());
+                }
             }
-            config_command.clone()
+            (**config_command).clone()
         }
         ConfigCommandType::NestedCommand(ref config_command) => {
             obtain_run_command_from_matches(&subcommand_matches, &config_command, args_map)
@@ -84,18 +119,52 @@ fn obtain_run_command_from_matches(
     }
 }
 
-fn get_run_command(config: &Config) -> Command {
-    let mut command = Command::new("run").arg_required_else_help(true);
+fn get_run_command(config: &Result<Config, CliError>) -> Command {
+    let command = run::RunOptions::augment_args(Command::new("run").arg_required_else_help(true));
+
+    match config {
+        Ok(config) => formulate_command(command, &config.commands),
+        // NOTE: accept any command so that running one reports the missing project
+        Err(_) => command.allow_external_subcommands(true),
+    }
+}
 
-    command = formulate_command(command, &config.commands);
-    command
+/// Reads `--project-dir` before the CLI is parsed, since the config of the
+/// project is needed to build the `run` command.
+fn get_project_dir_arg() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--project-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(dir) = arg.to_str().and_then(|a| a.strip_prefix("--project-dir=")) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    None
 }
 
 pub async fn init() -> ExitCode {
+    if let Some(project_dir) = get_project_dir_arg() {
+        set_project_dir(project_dir);
+    }
     let config = Config::new();
 
     let cli = Command::new("hit")
         .arg_required_else_help(true)
+        .arg(
+            Arg::new("project_dir")
+                .long("project-dir")
+                .value_name("DIR")
+                .global(true)
+                .help(format!(
+                    "Directory containing the .hit config, defaults to ${} or the nearest parent directory containing .hit",
+                    PROJECT_DIR_ENV_VAR
+                )),
+        )
         .subcommand(get_run_command(&config));
 
     let cli = StaticCommand::augment_subcommands(cli);
@@ -108,23 +177,34 @@ pub async fn init() -> ExitCode {
         "run" => {
             let run_subcommand_matches = matches.subcommand_matches("run").unwrap();
 
-            let mut args_map = HashMap::new();
-
-            let config_command = obtain_run_command_from_matches(
-                &run_subcommand_matches,
-                &config.commands,
-                &mut args_map,
-            );
-            run::run(&config_command, args_map).await
+            match config {
+                Ok(config) => {
+                    let mut args_map = HashMap::new();
+                    let config_command = obtain_run_command_from_matches(
+                        &run_subcommand_matches,
+                        &config.commands,
+                        &mut args_map,
+                    );
+                    let run_options =
+                        run::RunOptions::from_arg_matches(run_subcommand_matches).unwrap();
+                    run::run(&config_command, args_map, run_options).await
+                }
+                Err(e) => Err(e.into()),
+            }
         }
         _ => {
             let static_command_matches = StaticCommand::from_arg_matches(&matches).unwrap();
 
             match static_command_matches {
+                StaticCommand::Command(args) => command::init(args),
+                StaticCommand::Config(args) => config::init(args),
                 StaticCommand::Env(args) => env::init(args),
+                StaticCommand::Cookies(args) => cookies::init(args),
                 StaticCommand::Ephenv(args) => ephenv::init(args),
                 StaticCommand::Last(args) => last::init(args),
                 StaticCommand::Import(args) => import::init(args),
+                StaticCommand::Init(args) => init::init(args),
+                StaticCommand::State(args) => state::init(args),
             }
         }
     };
@@ -132,6 +212,9 @@ pub async fn init() -> ExitCode {
     if let Err(_e) = output {
         if let Some(e) = _e.downcast_ref::<CliError>() {
             eprintln!("{}", e);
+            if let Some(help) = &e.help {
+                eprintln!("help: {}", help);
+            }
         } else {
             panic!("{}", _e);
         }
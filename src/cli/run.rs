@@ -1,38 +1,352 @@
-use crate::core::app_config::get_app_config;
-use crate::core::command::Command;
+use crate::cli::state::format_size;
+use crate::core::app_config::{update_app_config, OmittedBody};
+use crate::core::command::{BodyType, Command, TlsConfig};
 use crate::core::config::Config;
-use crate::core::env::get_env;
-use crate::core::ephenv::get_ephenvs;
+use crate::core::cookies::{load_cookie_jar, save_cookie_jar};
+use crate::core::env::{get_env, resolve_env_vars};
+use crate::core::ephenv::get_ephenv_entries;
+use crate::core::graphql::{parse_variables, response_errors};
+use crate::core::params::{substitute_json, substitute_url};
+use crate::core::project::get_project_root;
+use crate::core::template::{get_variables, is_helper, undefined_variables, Renderer};
 use crate::utils::error::CliError;
-use crate::utils::http::handle_request;
+use crate::utils::grpc;
+use crate::utils::headers::Headers;
+use crate::utils::http::{
+    client, handle_request, unix_socket_url, Connection, Download, HttpMethod, NetworkConfig,
+    RequestBody, Response, ResponseSink, Stream,
+};
+use crate::utils::secret::Secrets;
+use crate::utils::stream::{Event, EventParser};
+use crate::utils::tls::describe_certificate;
+use crate::utils::websocket::{is_websocket_url, run_session, Message, Session};
+use clap::Args;
+use colored::Colorize;
 use colored_json;
 use edit::edit;
-use handlebars::Handlebars;
-use regex::Regex;
-use serde_json::Value;
-use std::collections::HashMap;
+use native_tls::TlsConnector;
+use reqwest_cookie_store::CookieStoreMutex;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
-use std::io::stdout;
+use std::fs;
 use std::io::Write;
+use std::io::{stdout, IsTerminal};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
-fn replace_params(input: String, params: &HashMap<String, String>) -> String {
-    params.keys().fold(input, |acc, x| {
-        acc.replace(&format!(":{}", x), &params.get(x).unwrap())
-    })
+#[derive(Args, Debug)]
+pub struct RunOptions {
+    /// Print details about how the request is built
+    #[arg(short, long)]
+    verbose: bool,
+    /// Print the request instead of sending it
+    #[arg(long)]
+    dry_run: bool,
+    /// Env to run the command in, overriding $HIT_ENV and the active env
+    #[arg(long)]
+    env: Option<String>,
+    /// Render undefined variables as empty instead of failing
+    #[arg(long)]
+    lenient: bool,
+    /// Save the response body to this file instead of printing it
+    #[arg(short, long, value_name = "PATH")]
+    output_file: Option<PathBuf>,
+    /// Stop after this many events of a streaming response or websocket messages
+    #[arg(long, value_name = "N")]
+    max_events: Option<usize>,
+    /// Skip verifying the TLS certificate of the server
+    #[arg(long)]
+    insecure: bool,
+    /// Send the request through this HTTP, HTTPS or SOCKS5 proxy, e.g.
+    /// socks5h://localhost:1080
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+    /// Connect to ADDRESS for requests to HOST:PORT instead of resolving HOST
+    #[arg(long, value_name = "HOST:PORT:ADDRESS")]
+    resolve: Vec<String>,
+    /// Send the request over this unix socket
+    #[arg(long, value_name = "PATH")]
+    unix_socket: Option<PathBuf>,
+    /// Neither send nor keep the cookies of the cookie jar
+    #[arg(long)]
+    no_cookies: bool,
+    /// Close a websocket session after this long, e.g. 10s
+    #[arg(long, value_parser = humantime::parse_duration)]
+    ws_timeout: Option<Duration>,
+}
+
+fn get_json_variables(value: &Value) -> BTreeSet<String> {
+    match value {
+        Value::String(string) => get_variables(string),
+        Value::Array(values) => values.iter().flat_map(get_json_variables).collect(),
+        Value::Object(map) => map
+            .iter()
+            .flat_map(|(key, value)| {
+                get_variables(key)
+                    .into_iter()
+                    .chain(get_json_variables(value))
+            })
+            .collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+fn get_template_variables(api_call: &Command) -> BTreeSet<String> {
+    let body_variables = match &api_call.body {
+        Some(body) => get_json_variables(body),
+        None => BTreeSet::new(),
+    };
+
+    std::iter::once(api_call.url.as_str())
+        .chain(api_call.headers.values())
+        .flat_map(get_variables)
+        .chain(body_variables)
+        .collect()
+}
+
+fn mask_response(response: &Response, secrets: &Secrets) -> Response {
+    Response {
+        url: secrets.mask(&response.url),
+        status: response.status,
+        headers: response
+            .headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), secrets.mask(v)))
+            .collect(),
+        body: if response.is_binary() {
+            response.body.clone()
+        } else {
+            secrets.mask(&response.text()).into_bytes()
+        },
+    }
+}
+
+fn print_progress(written: u64, total: Option<u64>) {
+    let progress = match total {
+        Some(total) => format!("{} / {}", format_size(written), format_size(total)),
+        None => format_size(written),
+    };
+    eprint!("\r\x1b[K{} {}", "downloading".dimmed(), progress);
+}
+
+fn print_connection(connection: &Connection) {
+    if let Some(certificate) = connection.peer_certificate {
+        eprintln!("{} {:?} over TLS", "protocol".dimmed(), connection.version);
+        eprintln!(
+            "{} {}",
+            "certificate".dimmed(),
+            describe_certificate(certificate)
+        );
+    }
+}
+
+fn print_json(json: &Value) {
+    let mut out = stdout();
+    colored_json::write_colored_json(json, &mut out).unwrap();
+    writeln!(out).unwrap();
+    out.flush().unwrap();
+}
+
+fn print_data(data: &str) {
+    match serde_json::from_str::<Value>(data) {
+        Ok(json) => print_json(&json),
+        Err(_) => {
+            let mut out = stdout();
+            writeln!(out, "{}", data).unwrap();
+            out.flush().unwrap();
+        }
+    }
+}
+
+fn print_event(event: &Event) {
+    if let Some(name) = &event.name {
+        println!("{}", format!("event: {}", name).dimmed());
+    }
+    print_data(&event.data);
+}
+
+fn print_message(message: &Message) {
+    match message {
+        Message::Text(text) => print_data(text),
+        Message::Binary(data) => println!(
+            "{}",
+            format!("binary message ({})", format_size(data.len() as u64)).dimmed()
+        ),
+    }
+}
+
+fn print_binary_body(response: &Response) {
+    if stdout().is_terminal() {
+        eprintln!(
+            "{}",
+            format!(
+                "binary response body ({}, {}) not printed, pass --output-file to save it",
+                response.content_type().unwrap_or("unknown content type"),
+                format_size(response.body.len() as u64)
+            )
+            .yellow()
+        );
+    } else {
+        let mut out = stdout();
+        out.write_all(&response.body).unwrap();
+        out.flush().unwrap();
+    }
+}
+
+fn print_dry_run(
+    api_call: &Command,
+    url: &str,
+    headers: &Headers,
+    body: Option<RequestBody>,
+    secrets: &Secrets,
+) {
+    println!("{} {}", api_call.method, secrets.mask(url));
+    let mut headers: Vec<(&str, &str)> = headers.iter().collect();
+    headers.sort_by_key(|(name, _)| *name);
+    for (name, value) in headers {
+        println!("{}: {}", name, secrets.mask(value));
+    }
+    if let Some(body) = body {
+        println!("\n{}", secrets.mask(&body.describe()));
+    }
+}
+
+/// Builds the body of a GraphQL request. The variables set in the body of the
+/// command are overridden by those provided as flags.
+fn graphql_body(
+    document: String,
+    body: Option<String>,
+    param_values: &HashMap<String, String>,
+) -> Result<String, Box<dyn Error>> {
+    let mut variables = match body.map(|body| serde_json::from_str::<Value>(&body)) {
+        Some(Ok(Value::Object(variables))) => variables,
+        Some(_) => {
+            return Err(Box::new(CliError {
+                message: "the body of a GraphQL command must be a JSON object of variables"
+                    .to_string(),
+                help: None,
+            }))
+        }
+        None => Map::new(),
+    };
+    for variable in parse_variables(&document)? {
+        if let Some(value) = param_values.get(&variable.name) {
+            variables.insert(variable.name.clone(), variable.value(value));
+        }
+    }
+    Ok(serde_json::to_string_pretty(
+        &json!({ "query": document, "variables": variables }),
+    )?)
+}
+
+fn websocket_message(api_call: &Command, body: String) -> Result<Message, CliError> {
+    match api_call.body_type {
+        // NOTE: JSON messages are sent compact, as most servers expect one
+        // message per line
+        BodyType::Json => Ok(Message::Text(
+            serde_json::from_str::<Value>(&body)
+                .map(|json| json.to_string())
+                .unwrap_or(body),
+        )),
+        BodyType::Text => Ok(Message::Text(body)),
+        BodyType::Binary => {
+            let RequestBody::File(path) = api_call.request_body(body, &get_project_root())? else {
+                unreachable!("binary bodies are always read from a file")
+            };
+            fs::read(&path).map(Message::Binary).map_err(|e| CliError {
+                message: format!("could not read {}: {}", path.display(), e),
+                help: None,
+            })
+        }
+        BodyType::Form | BodyType::Multipart => Err(CliError {
+            message: format!(
+                "a websocket message cannot be a {} body",
+                api_call.body_type
+            ),
+            help: Some("use a json, text or binary body instead".to_string()),
+        }),
+    }
+}
+
+async fn run_websocket(
+    api_call: &Command,
+    url: &str,
+    headers: &Headers,
+    body: Option<String>,
+    options: &RunOptions,
+    connector: TlsConnector,
+) -> Result<(Response, usize), Box<dyn Error>> {
+    if api_call.method != HttpMethod::GET {
+        return Err(Box::new(CliError {
+            message: format!("websocket commands must use GET, got {}", api_call.method),
+            help: None,
+        }));
+    }
+    let initial_message = body
+        .map(|body| websocket_message(api_call, body))
+        .transpose()?;
+
+    let max_events = options.max_events;
+    let mut messages = 0;
+    let end = run_session(
+        url,
+        headers,
+        connector,
+        Session {
+            initial_message,
+            send_stdin: max_events.is_none() && options.ws_timeout.is_none(),
+            timeout: options.ws_timeout,
+            on_message: Box::new(|message| {
+                print_message(message);
+                messages += 1;
+                max_events.is_none_or(|max| messages < max)
+            }),
+        },
+    )
+    .await?;
+
+    if let Some(reason) = &end.closed_by_server {
+        eprintln!(
+            "{}",
+            format!("connection closed by the server ({})", reason).dimmed()
+        );
+    }
+    Ok((end.handshake, messages))
+}
+
+/// The network settings of the env, overridden by those passed as flags.
+fn network_config(
+    env_network: Option<&NetworkConfig>,
+    options: &RunOptions,
+    render: impl Fn(&str) -> Result<String, CliError>,
+) -> Result<NetworkConfig, CliError> {
+    let mut network = env_network.cloned().unwrap_or_default();
+    network.proxy = match &options.proxy {
+        Some(proxy) => Some(proxy.clone()),
+        None => network.proxy.as_deref().map(render).transpose()?,
+    };
+    network.resolve.extend(options.resolve.iter().cloned());
+    network.unix_socket = match &options.unix_socket {
+        Some(socket) => Some(socket.clone()),
+        None => network
+            .unix_socket
+            .map(|socket| get_project_root().join(socket)),
+    };
+    Ok(network)
 }
 
 pub async fn run(
     api_call: &Command,
     param_values: HashMap<String, String>,
+    options: RunOptions,
 ) -> Result<(), Box<dyn Error>> {
-    let config = Config::new();
-    let env_var_regex = Regex::new(r"\{\{\w+}}").unwrap();
-
-    let hb_handle = Handlebars::new();
+    let config = Config::new()?;
 
     let url = api_call.url.as_str();
 
-    let current_env = match get_env() {
+    let current_env = match options.env.clone().or_else(get_env) {
         Some(e) => e,
         None => {
             return Err(Box::new(CliError {
@@ -50,63 +364,322 @@ pub async fn run(
             }))
         }
     };
-    let ephenv_data = get_ephenvs();
+    let ephenv_entries = get_ephenv_entries(Some(&current_env));
+    if options.verbose {
+        let mut names: Vec<&String> = ephenv_entries.keys().collect();
+        names.sort();
+        for name in names {
+            let mut source = match ephenv_entries[name].env {
+                Some(ref env) => format!("ephenv scoped to {}", env),
+                None => "ephenv".to_string(),
+            };
+            if env_data.contains_key(name) {
+                source.push_str(&format!(", overriding env {}", current_env));
+            }
+            eprintln!("{} {} ({})", "variable".dimmed(), name, source);
+        }
+    }
+    let ephenv_data: HashMap<String, String> = ephenv_entries
+        .into_iter()
+        .map(|(name, entry)| (name, entry.ephenv.value().clone()))
+        .collect();
+
+    // NOTE: secret references are only resolved when they are used by the
+    // command and not overridden by an ephenv
+    let mut secrets = Secrets::default();
+    let tls_config = TlsConfig::merge(config.tls.get(&current_env), api_call.tls.as_ref());
+    let mut used_variables = get_template_variables(api_call);
+    if let Some(password) = &tls_config.client_cert_password {
+        used_variables.extend(get_variables(password));
+    }
+    let env_network = config.network.get(&current_env);
+    if let Some(proxy) = env_network.and_then(|network| network.proxy.as_ref()) {
+        if options.proxy.is_none() {
+            used_variables.extend(get_variables(proxy));
+        }
+    }
+    let env_data = resolve_env_vars(
+        env_data,
+        &used_variables
+            .iter()
+            .filter(|name| !ephenv_data.contains_key(*name))
+            .cloned()
+            .collect(),
+        &mut secrets,
+    )?;
+
     let merged_data = env_data
-        .clone()
         .into_iter()
         .chain(ephenv_data.clone())
         .collect::<HashMap<String, String>>();
 
-    let url_with_env_vars = if env_var_regex.is_match(url) {
-        hb_handle.render_template(url, &merged_data).unwrap()
-    } else {
-        url.to_string()
+    let undefined: Vec<&String> = used_variables
+        .iter()
+        .filter(|name| !merged_data.contains_key(*name) && !is_helper(name))
+        .collect();
+    if !options.lenient && !undefined.is_empty() {
+        return Err(Box::new(undefined_variables(&undefined, &current_env)));
+    }
+
+    let renderer = Renderer::new(merged_data, !options.lenient)?;
+    let render = |template: &str| renderer.render(template);
+
+    let url_to_call = substitute_url(url, &param_values, render)?;
+    let tls = tls_config.resolve(options.insecure, render)?;
+    let network = network_config(env_network, &options, render)?;
+
+    let headers = api_call
+        .headers
+        .iter()
+        .map(|(k, v)| Ok((k.to_string(), renderer.render(v)?)))
+        .collect::<Result<Headers, CliError>>()?;
+
+    let body = match &api_call.body {
+        Some(body) => Some(match substitute_json(body, &param_values, &render)? {
+            Value::String(text) if !api_call.body_type.is_json() => text,
+            body => serde_json::to_string_pretty(&body)?,
+        }),
+        None => None,
+    };
+    let body = match api_call.graphql_document()? {
+        Some(document) => Some(graphql_body(document, body, &param_values)?),
+        None => body,
     };
+    for value in renderer.env_values() {
+        secrets.add(&value);
+    }
+
+    if let Some(grpc) = &api_call.grpc {
+        if api_call.method != HttpMethod::POST {
+            return Err(Box::new(CliError {
+                message: format!("gRPC commands must use POST, got {}", api_call.method),
+                help: None,
+            }));
+        }
+        if options.output_file.is_some() {
+            return Err(Box::new(CliError {
+                message: "--output-file is not supported for gRPC commands".to_string(),
+                help: None,
+            }));
+        }
+        network.check_supported("gRPC")?;
+        if options.dry_run {
+            let url = format!(
+                "{}/{}/{}",
+                url_to_call.trim_end_matches('/'),
+                grpc.service,
+                grpc.method
+            );
+            let body = body.map(RequestBody::Json);
+            print_dry_run(api_call, &url, &headers, body, &secrets);
+            return Ok(());
+        }
+    }
 
-    let url_to_call = replace_params(url_with_env_vars, &param_values);
-
-    let input = if api_call.body.is_some() {
-        Some(
-            edit(replace_params(
-                hb_handle
-                    .render_template(
-                        &serde_json::to_string_pretty(&api_call.body).unwrap(),
-                        &merged_data,
-                    )
-                    .unwrap(),
-                &param_values,
-            ))
-            .expect("Unable to open system editor"),
+    if is_websocket_url(&url_to_call) {
+        network.check_supported("websocket")?;
+    }
+
+    if options.dry_run {
+        let body = body
+            .map(|body| api_call.request_body(body, &get_project_root()))
+            .transpose()?;
+        print_dry_run(api_call, &url_to_call, &headers, body, &secrets);
+        return Ok(());
+    }
+
+    if options.insecure {
+        eprintln!(
+            "{}",
+            "warning: --insecure skips verifying the TLS certificate of the server, the response may not come from who you think"
+                .red()
+                .bold()
+        );
+    }
+    let input = match body {
+        Some(body) if api_call.body_type != BodyType::Binary => {
+            Some(edit(body).expect("Unable to open system editor"))
+        }
+        body => body,
+    };
+    if is_websocket_url(&url_to_call) {
+        let (handshake, messages) = run_websocket(
+            api_call,
+            &url_to_call,
+            &headers,
+            input,
+            &options,
+            tls.connector()?,
         )
-    } else {
-        None
+        .await?;
+        let masked_response = mask_response(&handshake, &secrets);
+        update_app_config(|app_config| {
+            app_config.set_prev_request(
+                masked_response,
+                Some(OmittedBody::WebSocket { messages }),
+                &current_env,
+            )
+        });
+        return Ok(());
+    }
+    let show_progress = std::io::stderr().is_terminal();
+    let max_events = options.max_events;
+    let mut events = 0;
+    let (response, streamed) = match &api_call.grpc {
+        Some(grpc) => {
+            let protos: Vec<PathBuf> = grpc
+                .protos
+                .iter()
+                .map(|path| get_project_root().join(path))
+                .collect();
+            let reply = grpc::call(grpc::Call {
+                url: &url_to_call,
+                service: &grpc.service,
+                method: &grpc.method,
+                protos: &protos,
+                headers: &headers,
+                tls: &tls,
+                body: input.as_deref(),
+                on_message: Box::new(|message| {
+                    print_json(message);
+                    events += 1;
+                    max_events.is_none_or(|max| events < max)
+                }),
+            })
+            .await?;
+            (reply.response, reply.streamed)
+        }
+        None => {
+            let input = input
+                .map(|input| api_call.request_body(input, &get_project_root()))
+                .transpose()?;
+
+            let mut parser: Option<EventParser> = None;
+            let sink = match options.output_file.as_deref() {
+                Some(path) => ResponseSink::Download(Download {
+                    path,
+                    on_progress: Box::new(move |written, total| {
+                        if show_progress {
+                            print_progress(written, total)
+                        }
+                    }),
+                }),
+                None => ResponseSink::Stream(Stream {
+                    force: api_call.stream,
+                    on_chunk: Box::new(|head, chunk| {
+                        let parser = parser.get_or_insert_with(|| {
+                            EventParser::new(head.content_type() == Some("text/event-stream"))
+                        });
+                        for event in parser.feed(chunk) {
+                            print_event(&event);
+                            events += 1;
+                            if max_events.is_some_and(|max| events >= max) {
+                                return false;
+                            }
+                        }
+                        true
+                    }),
+                }),
+            };
+            let (url_to_call, network) = match unix_socket_url(&url_to_call) {
+                Some((socket, url)) => (
+                    url,
+                    NetworkConfig {
+                        unix_socket: Some(socket),
+                        ..network
+                    },
+                ),
+                None => (url_to_call, network),
+            };
+            let verbose = options.verbose;
+            let use_jar = api_call.cookies.unwrap_or(config.cookies) && !options.no_cookies;
+            let jar =
+                use_jar.then(|| Arc::new(CookieStoreMutex::new(load_cookie_jar(&current_env))));
+            let response = handle_request(
+                &client(&url_to_call, &tls, &network, jar.clone(), verbose)?,
+                url_to_call,
+                &api_call.method,
+                &headers,
+                input,
+                sink,
+                Box::new(move |connection| {
+                    if verbose {
+                        print_connection(connection)
+                    }
+                }),
+            )
+            .await?;
+            if let Some(jar) = jar {
+                save_cookie_jar(&current_env, &jar.lock().unwrap());
+            }
+
+            if let Some(parser) = &mut parser {
+                for event in parser.finish() {
+                    if max_events.is_some_and(|max| events >= max) {
+                        break;
+                    }
+                    print_event(&event);
+                    events += 1;
+                }
+            }
+            (response, parser.is_some())
+        }
     };
 
-    let response = handle_request(
-        url_to_call,
-        &api_call.method,
-        &api_call
-            .headers
-            .clone()
-            .into_iter()
-            .map(|(k, v)| (k, hb_handle.render_template(&v, &merged_data).unwrap()))
-            .collect::<HashMap<String, String>>(),
-        input,
-    )
-    .await
-    .unwrap();
+    let omitted_body = match (options.output_file.as_deref(), streamed) {
+        (Some(path), _) => Some(OmittedBody::SavedToFile {
+            path: fs::canonicalize(path)
+                .unwrap_or(path.to_path_buf())
+                .to_string_lossy()
+                .into_owned(),
+            size: fs::metadata(path).map(|m| m.len()).unwrap_or_default(),
+        }),
+        (None, true) => Some(OmittedBody::Streamed { events }),
+        (None, false) => None,
+    };
+    let masked_response = mask_response(&response, &secrets);
+    update_app_config(|app_config| {
+        app_config.set_prev_request(masked_response, omitted_body.clone(), &current_env)
+    });
 
-    get_app_config().set_prev_request(response.clone());
+    match omitted_body {
+        Some(OmittedBody::SavedToFile { size, .. }) => {
+            if show_progress {
+                eprint!("\r\x1b[K");
+            }
+            eprintln!(
+                "saved {} to {}",
+                format_size(size),
+                options.output_file.unwrap().display()
+            );
+            return Ok(());
+        }
+        Some(_) => return Ok(()),
+        None => {}
+    }
+    if response.is_binary() {
+        print_binary_body(&response);
+        return Ok(());
+    }
 
-    let response_json_result = serde_json::from_str::<Value>(response.clone().body.as_str());
+    let response_json_result = serde_json::from_slice::<Value>(&response.body);
 
     match response_json_result {
         Ok(response_json) => {
+            // NOTE: only the data of a GraphQL response is printed, its errors
+            // are reported once the postscript has run
+            let (output, errors) = match (&api_call.graphql, response_json.get("data")) {
+                (Some(_), Some(data)) => (data, response_errors(&response_json)),
+                (Some(_), None) => (&response_json, response_errors(&response_json)),
+                (None, _) => (&response_json, Vec::new()),
+            };
             let mut out = stdout();
-            colored_json::write_colored_json(&response_json, &mut out).unwrap();
-            out.flush().unwrap();
-            writeln!(out, "").unwrap();
-            let mut postscript_env_vars = merged_data.clone();
+            if errors.is_empty() || !output.is_null() {
+                colored_json::write_colored_json(output, &mut out).unwrap();
+                out.flush().unwrap();
+                writeln!(out, "").unwrap();
+            }
+            let mut postscript_env_vars = renderer.data().clone();
             postscript_env_vars.extend(param_values);
 
             api_call
@@ -115,9 +688,23 @@ pub async fn run(
                     &postscript_env_vars,
                 )
                 .unwrap();
+
+            if !errors.is_empty() {
+                return Err(Box::new(CliError {
+                    message: format!(
+                        "the GraphQL request failed:\n{}",
+                        errors
+                            .iter()
+                            .map(|error| format!("  {}", error))
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    ),
+                    help: None,
+                }));
+            }
         }
         Err(_error) => {
-            println!("{}", response.body);
+            println!("{}", response.text());
         }
     };
 
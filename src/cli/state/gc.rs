@@ -0,0 +1,52 @@
+use super::format_size;
+use crate::constants::CONFIG_DIR;
+use crate::core::app_config::{get_responses_usage, update_app_config};
+use crate::core::ephenv::now;
+use clap::Args;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct StateGcArguments {
+    /// Also remove the state of projects not used for this long, e.g. 30days
+    #[arg(long, value_parser = humantime::parse_duration)]
+    older_than: Option<Duration>,
+}
+
+pub fn init(args: StateGcArguments) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, responses_size) = get_responses_usage();
+
+    let removed_projects = update_app_config(|app_config| {
+        app_config.remove_expired_ephenvs();
+
+        let mut removed_projects = Vec::new();
+        for project in app_config.list_projects() {
+            let reason = if !Path::new(&project).join(CONFIG_DIR).is_dir() {
+                "no longer a hit project"
+            } else if args.older_than.is_some_and(|older_than| {
+                let last_used = app_config
+                    .get_project_last_used(&project)
+                    .unwrap_or_default();
+                now().saturating_sub(last_used) >= older_than.as_secs()
+            }) {
+                "not used recently"
+            } else {
+                continue;
+            };
+            app_config.remove_project(&project);
+            removed_projects.push((project, reason));
+        }
+        removed_projects
+    });
+
+    for (project, reason) in &removed_projects {
+        println!("removed {} ({})", project, reason);
+    }
+    let (_, remaining_responses_size) = get_responses_usage();
+    println!(
+        "removed {} project(s), freed {} of response bodies",
+        removed_projects.len(),
+        format_size(responses_size.saturating_sub(remaining_responses_size))
+    );
+    Ok(())
+}
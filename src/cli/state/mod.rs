@@ -0,0 +1,35 @@
+mod gc;
+mod show;
+
+use clap::Subcommand;
+use std::error::Error;
+
+#[derive(Subcommand, Debug)]
+pub enum StateCommand {
+    /// Show what is stored for each project
+    Show,
+    /// Remove stale state
+    Gc(gc::StateGcArguments),
+}
+
+pub fn init(command: StateCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        StateCommand::Show => show::init(),
+        StateCommand::Gc(args) => gc::init(args),
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
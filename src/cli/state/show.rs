@@ -0,0 +1,61 @@
+use super::format_size;
+use crate::core::app_config::{get_app_config, get_app_config_file_path, get_responses_usage};
+use crate::core::ephenv::now;
+use crate::core::project::get_project_root;
+use colored::Colorize;
+use std::fs;
+use std::time::Duration;
+
+pub fn init() -> Result<(), Box<dyn std::error::Error>> {
+    let app_config = get_app_config();
+    let state_file_path = get_app_config_file_path();
+    let state_file_size = fs::metadata(&state_file_path).map_or(0, |metadata| metadata.len());
+    let (response_count, responses_size) = get_responses_usage();
+
+    println!(
+        "state file: {} ({})",
+        state_file_path.display(),
+        format_size(state_file_size)
+    );
+    println!(
+        "response bodies: {} ({})",
+        response_count,
+        format_size(responses_size)
+    );
+
+    let current_project = get_project_root().to_string_lossy().into_owned();
+    for project in app_config.list_projects() {
+        println!();
+        if project == current_project {
+            println!("{} {}", project.bold(), "(current)".dimmed());
+        } else {
+            println!("{}", project.bold());
+        }
+
+        if let Some(env) = app_config.get_project_env(&project) {
+            println!("  env: {}", env);
+        }
+        let ephenv_count = app_config.get_project_ephenv_count(&project);
+        if ephenv_count > 0 {
+            println!("  ephenvs: {}", ephenv_count);
+        }
+        let cookie_count = app_config.get_project_cookie_count(&project);
+        if cookie_count > 0 {
+            println!("  cookies: {}", cookie_count);
+        }
+        let envs = app_config.get_project_prev_request_envs(&project);
+        if !envs.is_empty() {
+            let size: u64 = envs
+                .iter()
+                .filter_map(|env| app_config.get_project_prev_request(&project, Some(env)))
+                .map(|response| response.body_size())
+                .sum();
+            println!("  responses: {} ({})", envs.join(", "), format_size(size));
+        }
+        if let Some(last_used) = app_config.get_project_last_used(&project) {
+            let ago = Duration::from_secs(now().saturating_sub(last_used));
+            println!("  last used: {} ago", humantime::format_duration(ago));
+        }
+    }
+    Ok(())
+}
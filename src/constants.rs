@@ -1 +1,5 @@
 pub const CONFIG_DIR: &str = ".hit";
+pub const CONFIG_FILE: &str = "config.json";
+pub const POSTSCRIPTS_DIR: &str = "postscripts";
+pub const CONFIG_SCHEMA_URL: &str =
+    "https://raw.githubusercontent.com/meshde/hit-cli/master/schema/config.schema.json";
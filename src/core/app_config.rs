@@ -1,21 +1,164 @@
+use crate::core::ephenv::{now, Ephenv};
+use crate::core::project::get_project_root;
+use crate::utils::headers::Headers;
 use crate::utils::http::Response;
+use colored::Colorize;
+use cookie_store::Cookie;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::env;
-use std::fs::{create_dir_all, File};
+use std::fs::{self, create_dir_all, rename, File, OpenOptions};
 use std::io::{BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+const RESPONSES_DIR: &str = "responses";
+/// Response bodies larger than this are not stored.
+pub const MAX_STORED_BODY_SIZE: usize = 1024 * 1024;
+/// The number of projects to keep state for. The least recently used ones are
+/// evicted first.
+pub const MAX_PROJECTS: usize = 100;
+
+/// A response kept in the app config, with its body stored in a separate file.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct StoredResponse {
+    pub url: String,
+    pub status: u16,
+    pub headers: Headers,
+    // NOTE: bodies used to be stored inline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub omitted_body: Option<OmittedBody>,
+}
+
+/// Why the body of a stored response was not stored, along with what is known
+/// of it.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum OmittedBody {
+    Binary {
+        content_type: Option<String>,
+        size: u64,
+    },
+    TooLarge {
+        size: u64,
+    },
+    SavedToFile {
+        path: String,
+        size: u64,
+    },
+    Streamed {
+        events: usize,
+    },
+    #[serde(rename = "websocket")]
+    WebSocket {
+        messages: usize,
+    },
+}
+
+impl StoredResponse {
+    /// Stores `response`, or only its metadata when its body is omitted,
+    /// binary or too large.
+    fn new(response: Response, omitted_body: Option<OmittedBody>) -> StoredResponse {
+        let omitted_body = match omitted_body {
+            Some(omitted_body) => Some(omitted_body),
+            None if response.is_binary() => Some(OmittedBody::Binary {
+                content_type: response.content_type().map(str::to_string),
+                size: response.body.len() as u64,
+            }),
+            None if response.body.len() > MAX_STORED_BODY_SIZE => Some(OmittedBody::TooLarge {
+                size: response.body.len() as u64,
+            }),
+            None => None,
+        };
+        if omitted_body.is_some() {
+            return StoredResponse {
+                url: response.url,
+                status: response.status,
+                headers: response.headers,
+                body: None,
+                body_file: None,
+                omitted_body,
+            };
+        }
+
+        let body = response.body;
+        let responses_dir = get_responses_dir();
+        create_dir_all(&responses_dir).expect("could not create responses dir");
+        let mut file = tempfile::Builder::new()
+            .suffix(".body")
+            .tempfile_in(&responses_dir)
+            .expect("could not create response body file");
+        file.write_all(&body).expect("could not save response body");
+        let (_, body_path) = file.keep().expect("could not save response body");
+
+        StoredResponse {
+            url: response.url,
+            status: response.status,
+            headers: response.headers,
+            body: None,
+            body_file: Some(
+                body_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            omitted_body: None,
+        }
+    }
+
+    pub fn body(&self) -> String {
+        match (&self.body, &self.body_file) {
+            (Some(body), _) => body.clone(),
+            (None, Some(body_file)) => {
+                fs::read_to_string(get_responses_dir().join(body_file)).unwrap_or_default()
+            }
+            (None, None) => String::new(),
+        }
+    }
+
+    pub fn body_size(&self) -> u64 {
+        match (&self.body, &self.body_file) {
+            (Some(body), _) => body.len() as u64,
+            (None, Some(body_file)) => fs::metadata(get_responses_dir().join(body_file))
+                .map(|metadata| metadata.len())
+                .unwrap_or_default(),
+            (None, None) => 0,
+        }
+    }
+
+    pub fn to_response(&self) -> Response {
+        Response {
+            url: self.url.clone(),
+            status: self.status,
+            headers: self.headers.clone(),
+            body: self.body().into_bytes(),
+        }
+    }
+}
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct AppConfig {
     #[serde(default)]
     envs: HashMap<String, String>,
     #[serde(default)]
-    ephenvs: HashMap<String, HashMap<String, String>>,
+    ephenvs: HashMap<String, HashMap<String, Ephenv>>,
+    #[serde(default)]
+    env_ephenvs: HashMap<String, HashMap<String, HashMap<String, Ephenv>>>,
+    #[serde(default)]
+    prev_request: HashMap<String, StoredResponse>,
     #[serde(default)]
-    prev_request: HashMap<String, Response>,
+    env_prev_requests: HashMap<String, HashMap<String, StoredResponse>>,
+    #[serde(default)]
+    cookies: HashMap<String, HashMap<String, Vec<Cookie<'static>>>>,
+    #[serde(default)]
+    last_used: HashMap<String, u64>,
 }
 
 impl AppConfig {
@@ -23,23 +166,31 @@ impl AppConfig {
         AppConfig {
             envs: HashMap::new(),
             ephenvs: HashMap::new(),
+            env_ephenvs: HashMap::new(),
             prev_request: HashMap::new(),
+            env_prev_requests: HashMap::new(),
+            cookies: HashMap::new(),
+            last_used: HashMap::new(),
         }
     }
 
-    pub fn save(&self) -> () {
-        let app_config_dir = get_app_config_dir();
+    /// Writes the app config to a temporary file and renames it over the
+    /// current one, so that a crash mid-write never leaves a truncated file.
+    fn save(&self) {
         let app_config_file_path = get_app_config_file_path();
+        let app_config_dir = app_config_file_path.parent().unwrap();
 
         let json_string = serde_json::to_string_pretty(&self).unwrap();
 
         create_dir_all(app_config_dir).expect("could not create app config dir");
         let mut file =
-            File::create(app_config_file_path).expect("could not create app config file");
+            NamedTempFile::new_in(app_config_dir).expect("could not create app config file");
 
         file.write_all(json_string.as_bytes())
             .expect("could not save app config");
-        file.sync_data().unwrap();
+        file.as_file().sync_data().unwrap();
+        file.persist(app_config_file_path)
+            .expect("could not save app config");
     }
 
     pub fn get_current_env(&self) -> Option<&String> {
@@ -48,39 +199,297 @@ impl AppConfig {
 
     pub fn set_current_env(&mut self, env: String) {
         self.envs.insert(get_config_key(), env);
-        self.save();
+        self.touch();
     }
 
-    pub fn get_ephenvs(&self) -> Option<&HashMap<String, String>> {
-        self.ephenvs.get(&get_config_key())
+    /// Moves the state of `from` to `to`: its ephenvs, last response and
+    /// cookie jar, and whether it is the current env.
+    pub fn rename_env(&mut self, from: &str, to: &str) {
+        let key = get_config_key();
+        if self.envs.get(&key).is_some_and(|env| env == from) {
+            self.envs.insert(key.clone(), to.to_string());
+        }
+        rename_env_state(self.env_ephenvs.get_mut(&key), from, to);
+        rename_env_state(self.env_prev_requests.get_mut(&key), from, to);
+        rename_env_state(self.cookies.get_mut(&key), from, to);
+        self.touch();
+    }
+
+    /// Drops the state of `env`, and unsets it if it is the current env.
+    pub fn remove_env(&mut self, env: &str) {
+        let key = get_config_key();
+        if self.envs.get(&key).is_some_and(|current| current == env) {
+            self.envs.remove(&key);
+        }
+        remove_env_state(&mut self.env_ephenvs, &key, env);
+        remove_env_state(&mut self.env_prev_requests, &key, env);
+        remove_env_state(&mut self.cookies, &key, env);
+        self.touch();
+    }
+
+    pub fn get_ephenv_scopes(&self) -> Vec<String> {
+        self.env_ephenvs
+            .get(&get_config_key())
+            .map(|scopes| scopes.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_ephenvs(&self, env: Option<&str>) -> HashMap<String, Ephenv> {
+        let ephenvs = match env {
+            Some(env) => self
+                .env_ephenvs
+                .get(&get_config_key())
+                .and_then(|scopes| scopes.get(env)),
+            None => self.ephenvs.get(&get_config_key()),
+        };
+        ephenvs.cloned().unwrap_or_default()
+    }
+
+    fn get_ephenvs_mut(&mut self, env: Option<&str>) -> &mut HashMap<String, Ephenv> {
+        match env {
+            Some(env) => self
+                .env_ephenvs
+                .entry(get_config_key())
+                .or_default()
+                .entry(env.to_string())
+                .or_default(),
+            None => self.ephenvs.entry(get_config_key()).or_default(),
+        }
+    }
+
+    pub fn set_ephenv(&mut self, key: String, ephenv: Ephenv, env: Option<String>) {
+        self.get_ephenvs_mut(env.as_deref()).insert(key, ephenv);
+        self.remove_expired_ephenvs();
+        self.touch();
+    }
+
+    pub fn unset_ephenv(&mut self, key: &str, env: Option<&str>) -> bool {
+        let removed = self.get_ephenvs_mut(env).remove(key).is_some();
+        self.remove_expired_ephenvs();
+        removed
+    }
+
+    /// Clears the ephenvs scoped to `env`, or all of them when no env is given.
+    pub fn clear_ephenvs(&mut self, env: Option<&str>) {
+        match env {
+            Some(env) => self.get_ephenvs_mut(Some(env)).clear(),
+            None => {
+                self.ephenvs.remove(&get_config_key());
+                self.env_ephenvs.remove(&get_config_key());
+            }
+        }
+        self.remove_expired_ephenvs();
+    }
+
+    pub fn remove_expired_ephenvs(&mut self) {
+        let scoped_ephenvs = self
+            .env_ephenvs
+            .values_mut()
+            .flat_map(|scopes| scopes.values_mut());
+        for ephenvs in self.ephenvs.values_mut().chain(scoped_ephenvs) {
+            ephenvs.retain(|_, ephenv| !ephenv.is_expired());
+        }
+        for scopes in self.env_ephenvs.values_mut() {
+            scopes.retain(|_, ephenvs| !ephenvs.is_empty());
+        }
+        self.ephenvs.retain(|_, ephenvs| !ephenvs.is_empty());
+        self.env_ephenvs.retain(|_, scopes| !scopes.is_empty());
     }
 
-    pub fn set_ephenv(&mut self, key: String, value: String) {
-        self.ephenvs
+    pub fn set_prev_request(
+        &mut self,
+        prev_request: Response,
+        omitted_body: Option<OmittedBody>,
+        env: &str,
+    ) {
+        let prev_request = StoredResponse::new(prev_request, omitted_body);
+        self.env_prev_requests
             .entry(get_config_key())
-            .and_modify(|data| {
-                data.insert(key.clone(), value.clone());
+            .or_default()
+            .insert(env.to_string(), prev_request.clone());
+        self.prev_request.insert(get_config_key(), prev_request);
+        self.touch();
+    }
+
+    /// Returns the last response received in `env`, or in any env when no env
+    /// is given.
+    pub fn get_prev_request(&self, env: Option<&str>) -> Option<&StoredResponse> {
+        self.get_project_prev_request(&get_config_key(), env)
+    }
+
+    pub fn get_project_prev_request(
+        &self,
+        project: &str,
+        env: Option<&str>,
+    ) -> Option<&StoredResponse> {
+        match env {
+            Some(env) => self
+                .env_prev_requests
+                .get(project)
+                .and_then(|requests| requests.get(env)),
+            None => self.prev_request.get(project),
+        }
+    }
+
+    pub fn get_project_prev_request_envs(&self, project: &str) -> Vec<String> {
+        let mut envs: Vec<String> = self
+            .env_prev_requests
+            .get(project)
+            .map(|requests| requests.keys().cloned().collect())
+            .unwrap_or_default();
+        envs.sort();
+        envs
+    }
+
+    /// Returns the cookie jar of `env`, by env, or the jars of every env when
+    /// no env is given.
+    pub fn get_cookies(&self, env: Option<&str>) -> Vec<(String, Vec<Cookie<'static>>)> {
+        let mut jars: Vec<(String, Vec<Cookie<'static>>)> = self
+            .cookies
+            .get(&get_config_key())
+            .map(|jars| {
+                jars.iter()
+                    .filter(|(jar_env, _)| env.is_none_or(|env| env == *jar_env))
+                    .map(|(env, cookies)| (env.clone(), cookies.clone()))
+                    .collect()
             })
-            .or_insert_with(|| {
-                let mut new_data = HashMap::new();
-                new_data.insert(key, value);
-                new_data
-            });
-        self.save();
+            .unwrap_or_default();
+        jars.sort_by(|(a, _), (b, _)| a.cmp(b));
+        jars
     }
 
-    pub fn set_prev_request(&mut self, prev_request: Response) {
-        self.prev_request.insert(get_config_key(), prev_request);
-        self.save();
+    pub fn set_cookies(&mut self, env: &str, cookies: Vec<Cookie<'static>>) {
+        let jars = self.cookies.entry(get_config_key()).or_default();
+        if cookies.is_empty() {
+            jars.remove(env);
+        } else {
+            jars.insert(env.to_string(), cookies);
+        }
+        self.cookies.retain(|_, jars| !jars.is_empty());
+        self.touch();
+    }
+
+    /// Clears the cookie jar of `env`, or of every env when no env is given.
+    pub fn clear_cookies(&mut self, env: Option<&str>) {
+        match env {
+            Some(env) => self.set_cookies(env, Vec::new()),
+            None => {
+                self.cookies.remove(&get_config_key());
+            }
+        }
+    }
+
+    pub fn get_project_cookie_count(&self, project: &str) -> usize {
+        self.cookies
+            .get(project)
+            .map(|jars| jars.values().map(|cookies| cookies.len()).sum())
+            .unwrap_or_default()
+    }
+
+    pub fn get_project_env(&self, project: &str) -> Option<&String> {
+        self.envs.get(project)
+    }
+
+    pub fn get_project_ephenv_count(&self, project: &str) -> usize {
+        let scoped_count: usize = self
+            .env_ephenvs
+            .get(project)
+            .map(|scopes| scopes.values().map(|ephenvs| ephenvs.len()).sum())
+            .unwrap_or_default();
+        scoped_count + self.ephenvs.get(project).map_or(0, |ephenvs| ephenvs.len())
+    }
+
+    /// Returns when the state of `project` was last changed, in seconds since
+    /// the epoch.
+    pub fn get_project_last_used(&self, project: &str) -> Option<u64> {
+        self.last_used.get(project).copied()
+    }
+
+    fn touch(&mut self) {
+        self.last_used.insert(get_config_key(), now());
+    }
+
+    /// Returns every project there is state for, sorted.
+    pub fn list_projects(&self) -> Vec<String> {
+        let projects: BTreeSet<&String> = self
+            .envs
+            .keys()
+            .chain(self.ephenvs.keys())
+            .chain(self.env_ephenvs.keys())
+            .chain(self.prev_request.keys())
+            .chain(self.env_prev_requests.keys())
+            .chain(self.cookies.keys())
+            .chain(self.last_used.keys())
+            .collect();
+        projects.into_iter().cloned().collect()
+    }
+
+    pub fn remove_project(&mut self, project: &str) {
+        self.envs.remove(project);
+        self.ephenvs.remove(project);
+        self.env_ephenvs.remove(project);
+        self.prev_request.remove(project);
+        self.env_prev_requests.remove(project);
+        self.cookies.remove(project);
+        self.last_used.remove(project);
+    }
+
+    /// Removes the least recently used projects beyond `MAX_PROJECTS`.
+    fn evict_projects(&mut self) {
+        let mut projects = self.list_projects();
+        if projects.len() <= MAX_PROJECTS {
+            return;
+        }
+        projects.sort_by_key(|project| self.get_project_last_used(project).unwrap_or_default());
+        for project in &projects[..projects.len() - MAX_PROJECTS] {
+            self.remove_project(project);
+        }
+    }
+
+    /// Deletes the stored response bodies that are no longer referenced, and
+    /// returns how many were deleted.
+    pub fn remove_orphaned_bodies(&self) -> usize {
+        let referenced: HashSet<&String> = self
+            .prev_request
+            .values()
+            .chain(
+                self.env_prev_requests
+                    .values()
+                    .flat_map(|requests| requests.values()),
+            )
+            .filter_map(|response| response.body_file.as_ref())
+            .collect();
+
+        let Ok(entries) = fs::read_dir(get_responses_dir()) else {
+            return 0;
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !referenced.contains(&entry.file_name().to_string_lossy().into_owned()))
+            .filter(|entry| fs::remove_file(entry.path()).is_ok())
+            .count()
     }
+}
+
+fn rename_env_state<T>(states: Option<&mut HashMap<String, T>>, from: &str, to: &str) {
+    if let Some(states) = states {
+        if let Some(state) = states.remove(from) {
+            states.insert(to.to_string(), state);
+        }
+    }
+}
 
-    pub fn get_prev_request(&self) -> Option<&Response> {
-        self.prev_request.get(&get_config_key())
+fn remove_env_state<T>(projects: &mut HashMap<String, HashMap<String, T>>, key: &str, env: &str) {
+    if let Some(states) = projects.get_mut(key) {
+        states.remove(env);
+        if states.is_empty() {
+            projects.remove(key);
+        }
     }
 }
 
 fn get_config_key() -> String {
-    env::current_dir().unwrap().to_string_lossy().into_owned()
+    get_project_root().to_string_lossy().into_owned()
 }
 
 fn get_app_config_dir() -> PathBuf {
@@ -90,26 +499,96 @@ fn get_app_config_dir() -> PathBuf {
         .to_path_buf()
 }
 
-pub fn get_app_config_file_path() -> String {
+pub fn get_app_config_file_path() -> PathBuf {
     let app_config_dir = if let Ok(env_dir) = env::var("APP_CONFIG_DIR") {
         PathBuf::from(env_dir)
     } else {
         get_app_config_dir()
     };
 
-    app_config_dir
-        .join("config.json")
-        .to_string_lossy()
-        .into_owned()
+    app_config_dir.join("config.json")
 }
 
-pub fn get_app_config() -> AppConfig {
+pub fn get_responses_dir() -> PathBuf {
+    get_app_config_file_path().with_file_name(RESPONSES_DIR)
+}
+
+/// Returns the number of stored response bodies and their total size in bytes.
+pub fn get_responses_usage() -> (usize, u64) {
+    let Ok(entries) = fs::read_dir(get_responses_dir()) else {
+        return (0, 0);
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .fold((0, 0), |(count, size), metadata| {
+            (count + 1, size + metadata.len())
+        })
+}
+
+fn read_app_config(path: &Path) -> Result<AppConfig, serde_json::Error> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file)),
+        Err(_) => Ok(AppConfig::new()),
+    }
+}
+
+/// Takes the exclusive lock guarding changes to the app config, which is held
+/// until the returned file is dropped.
+fn lock_app_config() -> File {
+    let config_file_path = get_app_config_file_path();
+    create_dir_all(config_file_path.parent().unwrap()).expect("could not create app config dir");
+
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(config_file_path.with_extension("json.lock"))
+        .expect("could not open app config lock file");
+    lock_file.lock().expect("could not lock app config");
+    lock_file
+}
+
+/// Reads the app config while holding the lock. A corrupt file is moved aside
+/// with a warning, and an empty app config is returned in its place.
+fn read_locked_app_config() -> AppConfig {
     let config_file_path = get_app_config_file_path();
-    if let Ok(file) = File::open(config_file_path) {
-        let reader = BufReader::new(file);
+    match read_app_config(&config_file_path) {
+        Ok(app_config) => app_config,
+        Err(e) => {
+            let backup_path = config_file_path.with_extension("json.corrupt");
+            let _ = rename(&config_file_path, &backup_path);
+            eprintln!(
+                "{} could not read {} ({}), starting over with an empty state; the old file was moved to {}",
+                "warning:".yellow(),
+                config_file_path.display(),
+                e,
+                backup_path.display()
+            );
+            AppConfig::new()
+        }
+    }
+}
 
-        let app_config = serde_json::from_reader(reader).unwrap();
-        return app_config;
+/// Reads the app config. A corrupt file is only moved aside under the lock,
+/// once read again, since another invocation may have replaced it meanwhile.
+pub fn get_app_config() -> AppConfig {
+    match read_app_config(&get_app_config_file_path()) {
+        Ok(app_config) => app_config,
+        Err(_) => {
+            let _lock = lock_app_config();
+            read_locked_app_config()
+        }
     }
-    AppConfig::new()
+}
+
+/// Applies `update` to the app config and saves it, while holding an exclusive
+/// lock so that concurrent invocations do not overwrite each other's changes.
+pub fn update_app_config<T>(update: impl FnOnce(&mut AppConfig) -> T) -> T {
+    let _lock = lock_app_config();
+    let mut app_config = read_locked_app_config();
+    let result = update(&mut app_config);
+    app_config.evict_projects();
+    app_config.save();
+    app_config.remove_orphaned_bodies();
+    result
 }
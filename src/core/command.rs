@@ -1,51 +1,296 @@
-use crate::constants::CONFIG_DIR;
-use crate::utils::http;
+use crate::constants::POSTSCRIPTS_DIR;
+use crate::core::graphql::{parse_variables, Variable};
+use crate::core::params::{get_json_params, get_params, starts_with_param};
+use crate::core::project::{get_config_dir, get_project_root};
+use crate::utils::error::CliError;
+use crate::utils::headers::Headers;
+use crate::utils::http::{self, Part, RequestBody};
+use crate::utils::tls::{Tls, TlsVersion};
 use array_tool::vec::Union;
+use clap::ValueEnum;
 use convert_case::{Case, Casing};
-use regex::Regex;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::io::{Error, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command as StdCommand;
 use tempfile::NamedTempFile;
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct PostScriptConfig {
     pub command: String,
     pub file: String,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// The gRPC method called by a command. The request and response types are
+/// resolved from `protos`, or from server reflection when there are none.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+pub struct GrpcConfig {
+    /// Fully qualified name of the service, e.g. `users.v1.Users`
+    pub service: String,
+    pub method: String,
+    /// `.proto` files, compiled with `protoc`, or descriptor sets, relative to
+    /// the project root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protos: Vec<String>,
+}
+
+/// The TLS settings of a command, or of an env under `tls`. Paths are
+/// relative to the project root.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct TlsConfig {
+    /// PEM files of CA certificates to trust along with the system ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_certs: Vec<String>,
+    /// PEM client certificate, or a PKCS#12 archive (`.p12` or `.pfx`)
+    /// holding the certificate and its key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<String>,
+    /// PEM (PKCS#8) key of a PEM client certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    /// Password of a PKCS#12 client certificate, which can use variables,
+    /// e.g. `{{CERT_PASSWORD}}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<TlsVersion>,
+}
+
+impl TlsConfig {
+    /// The settings of a command run in an env: the CA certificates of both
+    /// are trusted, and the other settings of the command take precedence.
+    pub fn merge(env: Option<&TlsConfig>, command: Option<&TlsConfig>) -> TlsConfig {
+        let (env, command) = match (env, command) {
+            (Some(env), Some(command)) => (env, command),
+            (env, command) => return env.or(command).cloned().unwrap_or_default(),
+        };
+        TlsConfig {
+            ca_certs: env.ca_certs.clone().union(command.ca_certs.clone()),
+            client_cert: command.client_cert.clone().or(env.client_cert.clone()),
+            client_key: match command.client_cert {
+                Some(_) => command.client_key.clone(),
+                None => command.client_key.clone().or(env.client_key.clone()),
+            },
+            client_cert_password: match command.client_cert {
+                Some(_) => command.client_cert_password.clone(),
+                None => command
+                    .client_cert_password
+                    .clone()
+                    .or(env.client_cert_password.clone()),
+            },
+            min_version: command.min_version.or(env.min_version),
+        }
+    }
+
+    pub fn resolve(
+        &self,
+        insecure: bool,
+        render: impl Fn(&str) -> Result<String, CliError>,
+    ) -> Result<Tls, CliError> {
+        let root = get_project_root();
+        Ok(Tls {
+            ca_certs: self.ca_certs.iter().map(|path| root.join(path)).collect(),
+            client_cert: self.client_cert.as_ref().map(|path| root.join(path)),
+            client_key: self.client_key.as_ref().map(|path| root.join(path)),
+            client_cert_password: self
+                .client_cert_password
+                .as_deref()
+                .map(render)
+                .transpose()?,
+            min_version: self.min_version,
+            insecure,
+        })
+    }
+}
+
+/// How the body of a command is sent. Form and multipart bodies are objects
+/// of fields, text bodies are strings, and binary bodies are the path of the
+/// file to send.
+#[derive(Deserialize, Serialize, JsonSchema, ValueEnum, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyType {
+    #[default]
+    Json,
+    Form,
+    Multipart,
+    Text,
+    Binary,
+}
+
+impl BodyType {
+    pub fn is_json(&self) -> bool {
+        *self == BodyType::Json
+    }
+
+    /// Builds the body to send from `text`, as rendered from the body of the
+    /// command and edited by the user.
+    pub fn to_request_body(
+        &self,
+        text: String,
+        files: &FileRoots,
+    ) -> Result<RequestBody, CliError> {
+        Ok(match self {
+            BodyType::Json => RequestBody::Json(text),
+            BodyType::Text => RequestBody::Text(text),
+            BodyType::Binary => RequestBody::File(
+                files.resolve("", file_path(&text).unwrap_or(PathBuf::from(text))),
+            ),
+            BodyType::Form => RequestBody::Form(
+                self.fields(&text)?
+                    .into_iter()
+                    .map(|(name, value)| (name, strip_file_escape(&value)))
+                    .collect(),
+            ),
+            BodyType::Multipart => RequestBody::Multipart(
+                self.fields(&text)?
+                    .into_iter()
+                    .map(|(name, value)| match file_path(&value) {
+                        Some(path) => {
+                            let path = files.resolve(&name, path);
+                            (name, Part::File(path))
+                        }
+                        None => (name, Part::Text(strip_file_escape(&value))),
+                    })
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Returns the fields of a form or multipart body. Arrays are sent as
+    /// repeated fields.
+    fn fields(&self, text: &str) -> Result<Vec<(String, String)>, CliError> {
+        let error = CliError {
+            message: format!("a {} body must be a JSON object of fields", self),
+            help: None,
+        };
+        let Ok(Value::Object(map)) = serde_json::from_str::<Value>(text) else {
+            return Err(error);
+        };
+        let mut fields = Vec::new();
+        for (name, value) in map {
+            let values = match value {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                match value {
+                    Value::String(value) => fields.push((name.clone(), value)),
+                    Value::Null => {}
+                    value => fields.push((name.clone(), value.to_string())),
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+impl std::fmt::Display for BodyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// Tells where the relative paths of the files of a body are resolved from.
+/// Files written in the config are relative to the project root, like every
+/// other path of the config, while those given by params are relative to the
+/// current directory.
+#[derive(Default)]
+pub struct FileRoots {
+    root: PathBuf,
+    /// Multipart fields whose files are written in the config, or the empty
+    /// name when it is the file of a binary body
+    config_fields: HashSet<String>,
+}
+
+impl FileRoots {
+    fn resolve(&self, field: &str, path: PathBuf) -> PathBuf {
+        if self.config_fields.contains(field) {
+            self.root.join(path)
+        } else {
+            path
+        }
+    }
+}
+
+/// Whether the value `template` of a body refers to a file whose path is
+/// written in the config rather than given by a param. Unless `bare`, the
+/// path must start with `@`.
+fn is_config_file(template: &Value, bare: bool) -> bool {
+    let Value::String(template) = template else {
+        return false;
+    };
+    let path = match file_path(template) {
+        Some(_) => &template[1..],
+        None if bare && !template.starts_with("@@") => template.as_str(),
+        None => return false,
+    };
+    !path.is_empty() && !starts_with_param(path)
+}
+
+/// Values starting with `@` refer to files. `@@` escapes a literal `@`.
+fn file_path(value: &str) -> Option<PathBuf> {
+    match value.strip_prefix('@') {
+        Some(path) if !path.starts_with('@') => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+fn strip_file_escape(value: &str) -> String {
+    match value.strip_prefix("@@") {
+        Some(rest) => format!("@{}", rest),
+        None => value.to_string(),
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
 pub struct Command {
     pub method: http::HttpMethod,
     pub url: String,
-    #[serde(default)]
-    pub headers: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Headers::is_empty")]
+    pub headers: Headers,
+    #[serde(default, skip_serializing_if = "BodyType::is_json")]
+    pub body_type: BodyType,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<Value>,
+    /// GraphQL query document, or `@path` of a `.graphql` file relative to
+    /// the project root. The body, if any, holds the values of its variables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<String>,
+    /// Call a gRPC method instead of sending an HTTP request. The body is the
+    /// request message as JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<GrpcConfig>,
+    /// TLS settings, merged with those of the env
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// Whether to use the cookie jar of the env, overriding the project
+    /// setting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookies: Option<bool>,
+    /// Print the response as it arrives, event by event, even when its
+    /// content type is not a streaming one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub postscript: Option<PostScriptConfig>,
-}
-
-fn get_params_from_string(input: &str) -> Vec<String> {
-    let route_param_regex = Regex::new(r":(\w+)").unwrap();
-    route_param_regex
-        .captures_iter(input)
-        .filter_map(|caps| caps.get(1))
-        .map(|word| word.as_str().to_string())
-        .collect::<HashSet<String>>()
-        .into_iter()
-        .collect()
+    /// Fields unknown to this version of hit, kept so that saving does not drop them
+    #[serde(flatten)]
+    #[schemars(skip)]
+    pub extra: IndexMap<String, Value>,
 }
 
 impl Command {
     pub fn route_params(&self) -> Vec<String> {
-        get_params_from_string(self.url.as_str())
+        get_params(&self.url).into_iter().collect()
     }
 
     pub fn body_params(&self) -> Vec<String> {
         match &self.body {
-            Some(input) => get_params_from_string(&input.to_string()),
+            Some(input) => get_json_params(input).into_iter().collect(),
             None => Vec::new(),
         }
     }
@@ -54,15 +299,66 @@ impl Command {
         self.route_params().union(self.body_params())
     }
 
+    /// Builds the body to send from `text`, resolving the files written in
+    /// the body of the command against `root`.
+    pub fn request_body(&self, text: String, root: &Path) -> Result<RequestBody, CliError> {
+        let config_fields = match (&self.body_type, &self.body) {
+            (BodyType::Binary, Some(body)) if is_config_file(body, true) => {
+                HashSet::from([String::new()])
+            }
+            (BodyType::Multipart, Some(Value::Object(fields))) => fields
+                .iter()
+                .filter(|(_, value)| match value {
+                    Value::Array(values) => values.iter().any(|value| is_config_file(value, false)),
+                    value => is_config_file(value, false),
+                })
+                .map(|(name, _)| name.clone())
+                .collect(),
+            _ => HashSet::new(),
+        };
+        let files = FileRoots {
+            root: root.to_path_buf(),
+            config_fields,
+        };
+        self.body_type.to_request_body(text, &files)
+    }
+
+    /// Returns the GraphQL query document of the command, reading it from its
+    /// file if needed.
+    pub fn graphql_document(&self) -> Result<Option<String>, CliError> {
+        let Some(document) = &self.graphql else {
+            return Ok(None);
+        };
+        match file_path(document) {
+            Some(path) => {
+                let path = get_project_root().join(path);
+                std::fs::read_to_string(&path)
+                    .map(Some)
+                    .map_err(|e| CliError {
+                        message: format!("could not read {}: {}", path.display(), e),
+                        help: None,
+                    })
+            }
+            None => Ok(Some(document.clone())),
+        }
+    }
+
+    /// Returns the variables declared by the GraphQL query document, or none
+    /// when it cannot be read or parsed, which is reported on run instead.
+    pub fn graphql_variables(&self) -> Vec<Variable> {
+        match self.graphql_document() {
+            Ok(Some(document)) => parse_variables(&document).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn run_post_command_script(
         &self,
         command_response: &str,
         env_vars: &HashMap<String, String>,
     ) -> Result<(), Error> {
         if let Some(postscript) = self.postscript.clone() {
-            let script_path = PathBuf::from(CONFIG_DIR)
-                .join("postscripts")
-                .join(postscript.file);
+            let script_path = get_config_dir().join(POSTSCRIPTS_DIR).join(postscript.file);
 
             if script_path.exists() {
                 let mut response_file = NamedTempFile::new()?;
@@ -99,12 +395,19 @@ mod tests {
         Command {
             method: http::HttpMethod::POST,
             url: String::from("https://example.com/orgs/:orgId/employees/:employeeId"),
-            headers: HashMap::new(),
+            headers: Headers::new(),
+            body_type: BodyType::Json,
             body: Some(json!({
                 "name": ":employeeName",
                 "title": ":title",
             })),
+            graphql: None,
+            grpc: None,
+            tls: None,
+            cookies: None,
+            stream: false,
             postscript: None,
+            extra: IndexMap::new(),
         }
     }
 
@@ -142,4 +445,103 @@ mod tests {
             ]
         )
     }
+
+    #[rstest]
+    #[case(BodyType::Text, "@./a.png", "@./a.png")]
+    #[case(BodyType::Binary, "@./a.png", "@./a.png")]
+    #[case(BodyType::Binary, "./a.png", "@./a.png")]
+    #[case(
+        BodyType::Form,
+        r#"{"a": "x y", "b": [1, "@@c"], "d": null}"#,
+        "a=x%20y&b=1&b=%40c"
+    )]
+    #[case(
+        BodyType::Multipart,
+        r#"{"name": "@@me", "avatar": "@./me.png"}"#,
+        "name: @me\navatar: @./me.png"
+    )]
+    fn test_to_request_body(
+        #[case] body_type: BodyType,
+        #[case] text: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            body_type
+                .to_request_body(text.to_string(), &FileRoots::default())
+                .unwrap()
+                .describe(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(BodyType::Binary, json!("data.bin"), "data.bin", "@/root/data.bin")]
+    #[case(BodyType::Binary, json!("@data.bin"), "@data.bin", "@/root/data.bin")]
+    #[case(BodyType::Binary, json!(":file"), "data.bin", "@data.bin")]
+    #[case(BodyType::Binary, json!("@:file"), "@data.bin", "@data.bin")]
+    #[case(
+        BodyType::Multipart,
+        json!({"logo": "@logo.png", "avatar": ":avatar", "name": "@@me"}),
+        r#"{"logo": "@logo.png", "avatar": "@me.png", "name": "@@me"}"#,
+        "logo: @/root/logo.png\navatar: @me.png\nname: @me"
+    )]
+    fn test_request_body_resolves_config_files(
+        input_command: Command,
+        #[case] body_type: BodyType,
+        #[case] body: Value,
+        #[case] text: &str,
+        #[case] expected: &str,
+    ) {
+        let command = Command {
+            body_type,
+            body: Some(body),
+            ..input_command
+        };
+        assert_eq!(
+            command
+                .request_body(text.to_string(), Path::new("/root"))
+                .unwrap()
+                .describe(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_form_body_must_be_an_object() {
+        let error = BodyType::Form
+            .to_request_body("[1]".to_string(), &FileRoots::default())
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "a form body must be a JSON object of fields");
+    }
+
+    #[rstest]
+    fn test_merge_tls_config() {
+        let env: TlsConfig = serde_json::from_value(json!({
+            "ca_certs": ["ca.pem"],
+            "client_cert": "client.p12",
+            "client_cert_password": "{{PASSWORD}}",
+            "min_version": "1.2"
+        }))
+        .unwrap();
+        let command: TlsConfig = serde_json::from_value(json!({
+            "ca_certs": ["ca.pem", "other-ca.pem"],
+            "client_cert": "admin.pem",
+            "client_key": "admin-key.pem"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            TlsConfig::merge(Some(&env), Some(&command)),
+            serde_json::from_value(json!({
+                "ca_certs": ["ca.pem", "other-ca.pem"],
+                "client_cert": "admin.pem",
+                "client_key": "admin-key.pem",
+                "min_version": "1.2"
+            }))
+            .unwrap()
+        );
+        assert_eq!(TlsConfig::merge(Some(&env), None), env);
+        assert_eq!(TlsConfig::merge(None, None), TlsConfig::default());
+    }
 }
@@ -1,57 +1,404 @@
-use crate::constants::CONFIG_DIR;
-use crate::core::command::Command;
+use crate::constants::{CONFIG_DIR, CONFIG_FILE, CONFIG_SCHEMA_URL};
+use crate::core::command::{Command, TlsConfig};
+use crate::core::env::EnvValue;
+use crate::core::project::get_config_dir;
+use crate::utils::error::CliError;
+use crate::utils::http::NetworkConfig;
+use crate::utils::jsonc;
+use indexmap::IndexMap;
+use jsonc_parser::{parse_to_serde_value, ParseOptions};
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
 use std::fs;
-use std::io::{BufReader, Write};
+use std::io::Write;
 use std::path::PathBuf;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
 pub struct Config {
-    pub envs: HashMap<String, HashMap<String, String>>,
-    pub commands: HashMap<String, Box<CommandType>>,
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    pub envs: IndexMap<String, IndexMap<String, EnvValue>>,
+    pub commands: IndexMap<String, Box<CommandType>>,
+    /// TLS settings of the commands run in an env, by env name
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub tls: IndexMap<String, TlsConfig>,
+    /// Proxy, address overrides and unix socket of the commands run in an
+    /// env, by env name
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub network: IndexMap<String, NetworkConfig>,
+    /// Keep the cookies set by responses in a jar per env, and send them
+    /// with later requests
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cookies: bool,
+    /// Fields unknown to this version of hit, kept so that saving does not drop them
+    #[serde(flatten)]
+    #[schemars(skip)]
+    pub extra: IndexMap<String, serde_json::Value>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
 #[serde(untagged)]
 pub enum CommandType {
-    Command(Command),
-    NestedCommand(HashMap<String, Box<CommandType>>),
+    Command(Box<Command>),
+    NestedCommand(IndexMap<String, Box<CommandType>>),
 }
 
-fn get_config_file_path() -> PathBuf {
-    PathBuf::from(CONFIG_DIR).join("config.json")
+fn split_command_path(path: &str) -> Result<Vec<&str>, CliError> {
+    let segments: Vec<&str> = path.split('/').collect();
+    if segments
+        .iter()
+        .any(|segment| segment.is_empty() || segment.contains(char::is_whitespace))
+    {
+        return Err(CliError {
+            message: format!("invalid command path `{}`", path),
+            help: Some("use names separated by `/`, e.g. users/get-by-id".to_string()),
+        });
+    }
+    Ok(segments)
+}
+
+fn merge_commands(
+    commands: &mut IndexMap<String, Box<CommandType>>,
+    other: IndexMap<String, Box<CommandType>>,
+) {
+    for (name, command_type) in other {
+        match (commands.get_mut(&name).map(|c| &mut **c), *command_type) {
+            (Some(CommandType::NestedCommand(existing)), CommandType::NestedCommand(nested)) => {
+                merge_commands(existing, nested)
+            }
+            (_, command_type) => {
+                commands.insert(name, Box::new(command_type));
+            }
+        }
+    }
+}
+
+pub fn get_config_file_path() -> PathBuf {
+    get_config_dir().join(CONFIG_FILE)
+}
+
+pub fn get_config_schema() -> serde_json::Value {
+    serde_json::to_value(schema_for!(Config)).unwrap()
 }
 
 impl Config {
-    pub fn new() -> Config {
+    pub fn empty() -> Config {
+        Config {
+            schema: Some(CONFIG_SCHEMA_URL.to_string()),
+            commands: IndexMap::new(),
+            envs: IndexMap::new(),
+            tls: IndexMap::new(),
+            network: IndexMap::new(),
+            cookies: false,
+            extra: IndexMap::new(),
+        }
+    }
+
+    pub fn example() -> Config {
+        let mut config: Config = serde_json::from_value(json!({
+            "envs": {
+                "prod": {
+                    "API_URL": "https://jsonplaceholder.typicode.com"
+                }
+            },
+            "commands": {
+                "posts": {
+                    "list": {
+                        "method": "GET",
+                        "url": "{{API_URL}}/posts"
+                    },
+                    "get": {
+                        "method": "GET",
+                        "url": "{{API_URL}}/posts/:postId"
+                    },
+                    "create": {
+                        "method": "POST",
+                        "url": "{{API_URL}}/posts",
+                        "headers": {
+                            "Content-Type": "application/json"
+                        },
+                        "body": {
+                            "title": ":title",
+                            "body": ":body",
+                            "userId": 1
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        config.schema = Some(CONFIG_SCHEMA_URL.to_string());
+        config
+    }
+
+    pub fn new() -> Result<Config, CliError> {
         let file_path = get_config_file_path();
+        if !file_path.exists() {
+            return Err(CliError {
+                message: "no hit project found".to_string(),
+                help: Some(format!(
+                    "run `hit init` to create one, or run hit from a directory containing {}",
+                    CONFIG_DIR
+                )),
+            });
+        }
 
-        // Create the directory if it doesn't exist
-        if let Some(parent_dir) = file_path.parent() {
-            fs::create_dir_all(parent_dir).expect("Failed to create directory");
+        let to_cli_error = |e: &dyn std::fmt::Display| CliError {
+            message: format!("could not read {}: {}", file_path.display(), e),
+            help: None,
+        };
+        let text = fs::read_to_string(&file_path).map_err(|e| to_cli_error(&e))?;
+        Config::parse(&text).map_err(|e| CliError {
+            message: to_cli_error(&e).message,
+            help: e.help,
+        })
+    }
+
+    /// Parses a config, allowing comments and trailing commas.
+    pub fn parse(text: &str) -> Result<Config, CliError> {
+        let config: Config =
+            parse_to_serde_value(text, &ParseOptions::default()).map_err(|e| CliError {
+                message: e.to_string(),
+                help: None,
+            })?;
+        let env_settings = config
+            .tls
+            .keys()
+            .map(|env| ("tls", env))
+            .chain(config.network.keys().map(|env| ("network", env)));
+        for (settings, env) in env_settings {
+            if !config.envs.contains_key(env) {
+                return Err(CliError {
+                    message: format!("`{}` settings of unknown env `{}`", settings, env),
+                    help: Some(format!(
+                        "add `{}` to `envs`, or remove its `{}` settings",
+                        env, settings
+                    )),
+                });
+            }
         }
+        Ok(config)
+    }
 
-        // Create the file if it doesn't exist
-        if !file_path.exists() {
-            let init_config = Config {
-                commands: HashMap::new(),
-                envs: HashMap::new(),
+    /// Adds the envs and commands of `other`. Commands of `other` replace the
+    /// ones with the same path, while groups are merged and existing env
+    /// variables are kept.
+    pub fn merge(&mut self, other: Config) {
+        for (env, vars) in other.envs {
+            let existing_vars = self.envs.entry(env).or_default();
+            for (name, value) in vars {
+                existing_vars.entry(name).or_insert(value);
+            }
+        }
+        merge_commands(&mut self.commands, other.commands);
+    }
+
+    pub fn get_command(&self, path: &str) -> Result<&CommandType, CliError> {
+        let segments = split_command_path(path)?;
+        let mut commands = &self.commands;
+        let mut found = None;
+
+        for (index, segment) in segments.iter().enumerate() {
+            let command_type = commands.get(*segment).ok_or_else(|| CliError {
+                message: format!("command `{}` not found", path),
+                help: None,
+            })?;
+            match **command_type {
+                CommandType::NestedCommand(ref nested_commands) => commands = nested_commands,
+                CommandType::Command(_) if index + 1 < segments.len() => {
+                    return Err(CliError {
+                        message: format!("command `{}` not found", path),
+                        help: None,
+                    })
+                }
+                CommandType::Command(_) => {}
+            }
+            found = Some(&**command_type);
+        }
+
+        Ok(found.unwrap())
+    }
+
+    pub fn insert_command(&mut self, path: &str, command: CommandType) -> Result<(), CliError> {
+        let segments = split_command_path(path)?;
+        let (name, parents) = segments.split_last().unwrap();
+        let mut commands = &mut self.commands;
+
+        for segment in parents {
+            let command_type = commands
+                .entry(segment.to_string())
+                .or_insert_with(|| Box::new(CommandType::NestedCommand(IndexMap::new())));
+            commands = match **command_type {
+                CommandType::NestedCommand(ref mut nested_commands) => nested_commands,
+                CommandType::Command(_) => {
+                    return Err(CliError {
+                        message: format!("`{}` is a command and cannot contain commands", segment),
+                        help: None,
+                    })
+                }
             };
+        }
 
-            init_config.save().expect("could not save initial config")
+        if commands.contains_key(*name) {
+            return Err(CliError {
+                message: format!("command `{}` already exists", path),
+                help: Some(format!("use `hit command edit {}` to change it", path)),
+            });
         }
+        commands.insert(name.to_string(), Box::new(command));
+        Ok(())
+    }
 
-        let file = fs::File::open(file_path).expect("config file missing");
-        let reader = BufReader::new(file);
+    pub fn remove_command(&mut self, path: &str) -> Result<CommandType, CliError> {
+        let segments = split_command_path(path)?;
+        remove_command_from(&mut self.commands, &segments).ok_or_else(|| CliError {
+            message: format!("command `{}` not found", path),
+            help: None,
+        })
+    }
 
-        let config: Config = serde_json::from_reader(reader).expect("Error while reading JSON");
-        return config;
+    /// Returns the config in the canonical formatting it is saved with.
+    pub fn to_formatted_string(&self) -> String {
+        serde_json::to_string_pretty(&self).unwrap() + "\n"
     }
+
+    /// Saves the config. Only the parts of an existing config file that
+    /// changed are rewritten, so that its comments are kept.
     pub fn save(&self) -> Result<(), std::io::Error> {
         let file_path = get_config_file_path();
+        let contents = fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|text| {
+                let old = serde_json::to_value(Config::parse(&text).ok()?).unwrap();
+                let new = serde_json::to_value(self).unwrap();
+                jsonc::update(&text, &old, &new).ok()
+            })
+            .unwrap_or_else(|| self.to_formatted_string());
+
         let mut file = fs::File::create(&file_path).expect("Failed to create file");
+        file.write_all(contents.as_bytes())
+    }
+}
+
+fn remove_command_from(
+    commands: &mut IndexMap<String, Box<CommandType>>,
+    segments: &[&str],
+) -> Option<CommandType> {
+    let (name, rest) = segments.split_first()?;
+    if rest.is_empty() {
+        return commands
+            .shift_remove(*name)
+            .map(|command_type| *command_type);
+    }
+
+    match **commands.get_mut(*name)? {
+        CommandType::NestedCommand(ref mut nested_commands) => {
+            let removed = remove_command_from(nested_commands, rest);
+            if nested_commands.is_empty() {
+                commands.shift_remove(*name);
+            }
+            removed
+        }
+        CommandType::Command(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::command::BodyType;
+    use crate::utils::headers::Headers;
+    use crate::utils::http::HttpMethod;
+    use rstest::*;
+
+    fn command(url: &str) -> CommandType {
+        CommandType::Command(Box::new(Command {
+            method: HttpMethod::GET,
+            url: url.to_string(),
+            headers: Headers::new(),
+            body_type: BodyType::Json,
+            body: None,
+            graphql: None,
+            grpc: None,
+            tls: None,
+            cookies: None,
+            stream: false,
+            postscript: None,
+            extra: IndexMap::new(),
+        }))
+    }
+
+    #[fixture]
+    fn config() -> Config {
+        let mut config = Config::empty();
+        config
+            .insert_command("users/get-by-id", command("/users/:id"))
+            .unwrap();
+        config
+    }
+
+    #[rstest]
+    fn test_insert_nested_command(config: Config) {
+        match config.get_command("users/get-by-id").unwrap() {
+            CommandType::Command(command) => assert_eq!(command.url, "/users/:id"),
+            CommandType::NestedCommand(_) => panic!("expected a command"),
+        }
+    }
+
+    #[rstest]
+    fn test_insert_existing_command_fails(mut config: Config) {
+        assert!(config
+            .insert_command("users/get-by-id", command("/other"))
+            .is_err());
+    }
+
+    #[rstest]
+    fn test_insert_below_command_fails(mut config: Config) {
+        assert!(config
+            .insert_command("users/get-by-id/more", command("/other"))
+            .is_err());
+    }
+
+    #[rstest]
+    fn test_merge_keeps_commands_of_groups(mut config: Config) {
+        config
+            .insert_command("users/custom", command("/users/custom"))
+            .unwrap();
+        let mut imported = Config::empty();
+        imported
+            .insert_command("users/get-by-id", command("/v2/users/:id"))
+            .unwrap();
+        imported
+            .insert_command("users/list", command("/users"))
+            .unwrap();
+
+        config.merge(imported);
+
+        let CommandType::NestedCommand(users) = &*config.commands["users"] else {
+            panic!("expected a group of commands");
+        };
+        assert_eq!(
+            users.keys().collect::<Vec<_>>(),
+            vec!["get-by-id", "custom", "list"]
+        );
+        match config.get_command("users/get-by-id").unwrap() {
+            CommandType::Command(command) => assert_eq!(command.url, "/v2/users/:id"),
+            CommandType::NestedCommand(_) => panic!("expected a command"),
+        }
+    }
+
+    #[rstest]
+    fn test_remove_command_prunes_empty_groups(mut config: Config) {
+        config.remove_command("users/get-by-id").unwrap();
+        assert!(config.commands.is_empty());
+    }
 
-        file.write_all(serde_json::to_string_pretty(&self).unwrap().as_bytes())
+    #[rstest]
+    #[case("")]
+    #[case("users//get")]
+    #[case("users/get by id")]
+    fn test_invalid_command_path(config: Config, #[case] path: &str) {
+        assert!(config.get_command(path).is_err());
     }
 }
@@ -0,0 +1,44 @@
+use crate::core::app_config::{get_app_config, update_app_config};
+use cookie_store::{Cookie, CookieStore};
+use std::convert::Infallible;
+
+/// Loads the cookie jar of `env` in the current project.
+pub fn load_cookie_jar(env: &str) -> CookieStore {
+    let cookies = get_app_config()
+        .get_cookies(Some(env))
+        .into_iter()
+        .flat_map(|(_, cookies)| cookies);
+    CookieStore::from_cookies(cookies.map(Ok::<_, Infallible>), false).unwrap()
+}
+
+/// Saves the unexpired cookies of `jar` as the cookie jar of `env`. Session
+/// cookies are kept too, until the jar is cleared.
+pub fn save_cookie_jar(env: &str, jar: &CookieStore) {
+    let mut cookies: Vec<Cookie<'static>> = jar.iter_unexpired().cloned().collect();
+    cookies.sort_by(|a, b| {
+        (a.domain.as_cow(), &*a.path, a.name()).cmp(&(b.domain.as_cow(), &*b.path, b.name()))
+    });
+    update_app_config(|app_config| app_config.set_cookies(env, cookies));
+}
+
+/// Returns the unexpired cookies of `env`, or of every env when no env is
+/// given, by env.
+pub fn list_cookies(env: Option<&str>) -> Vec<(String, Vec<Cookie<'static>>)> {
+    get_app_config()
+        .get_cookies(env)
+        .into_iter()
+        .map(|(env, cookies)| {
+            (
+                env,
+                cookies
+                    .into_iter()
+                    .filter(|cookie| !cookie.is_expired())
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+pub fn clear_cookies(env: Option<&str>) {
+    update_app_config(|app_config| app_config.clear_cookies(env));
+}
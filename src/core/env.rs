@@ -1,25 +1,314 @@
-use crate::core::app_config::get_app_config;
+use crate::constants::CONFIG_DIR;
+use crate::core::app_config::{get_app_config, update_app_config};
 use crate::core::config::Config;
+use crate::core::project::{get_config_dir, get_project_root};
+use crate::utils::error::CliError;
+use crate::utils::secret::Secrets;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::process::Command as StdCommand;
 
-pub fn get_env() -> Option<String> {
-    let app_config = get_app_config();
-    if let Some(env) = app_config.get_current_env() {
-        return Some(env.clone());
+const DOTENV_FILES: [&str; 2] = [".env", ".env.local"];
+
+/// The value of an env variable: either the value itself or a reference to
+/// where the value should be read from when running a command.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum EnvValue {
+    Value(String),
+    FromEnv { from_env: String },
+    FromFile { from_file: String },
+    Command { command: String },
+}
+
+impl EnvValue {
+    pub fn is_reference(&self) -> bool {
+        !matches!(self, EnvValue::Value(_))
+    }
+
+    /// Describes where a referenced value comes from.
+    pub fn source(&self) -> Option<String> {
+        match self {
+            EnvValue::Value(_) => None,
+            EnvValue::FromEnv { from_env } => Some(format!("from env var {}", from_env)),
+            EnvValue::FromFile { from_file } => Some(format!("from file {}", from_file)),
+            EnvValue::Command { command } => Some(format!("from command `{}`", command)),
+        }
+    }
+
+    pub fn resolve(
+        &self,
+        name: &str,
+        dotenv_vars: &HashMap<String, String>,
+    ) -> Result<String, CliError> {
+        match self {
+            EnvValue::Value(value) => Ok(value.clone()),
+            EnvValue::FromEnv { from_env } => std::env::var(from_env)
+                .ok()
+                .or_else(|| dotenv_vars.get(from_env).cloned())
+                .ok_or_else(|| CliError {
+                    message: format!(
+                        "environment variable `{}` used by `{}` is not set",
+                        from_env, name
+                    ),
+                    help: Some(format!("export it or add it to {}/.env", CONFIG_DIR)),
+                }),
+            EnvValue::FromFile { from_file } => {
+                fs::read_to_string(get_project_root().join(from_file))
+                    .map(|value| value.trim_end_matches(['\r', '\n']).to_string())
+                    .map_err(|e| CliError {
+                        message: format!(
+                            "could not read `{}` used by `{}`: {}",
+                            from_file, name, e
+                        ),
+                        help: None,
+                    })
+            }
+            EnvValue::Command { command } => run_secret_command(command, name),
+        }
     }
-    None
 }
 
-pub fn set_env(env: String) -> () {
-    let mut app_config = get_app_config();
-    app_config.set_current_env(env);
+impl From<String> for EnvValue {
+    fn from(value: String) -> Self {
+        EnvValue::Value(value)
+    }
+}
+
+fn run_secret_command(command: &str, name: &str) -> Result<String, CliError> {
+    let output = if cfg!(windows) {
+        StdCommand::new("cmd").args(["/C", command]).output()
+    } else {
+        StdCommand::new("sh").args(["-c", command]).output()
+    }
+    .map_err(|e| CliError {
+        message: format!("could not run `{}` used by `{}`: {}", command, name, e),
+        help: None,
+    })?;
+
+    if !output.status.success() {
+        return Err(CliError {
+            message: format!("`{}` used by `{}` failed: {}", command, name, output.status),
+            help: Some(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                .filter(|stderr| !stderr.is_empty()),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string())
+}
+
+/// Reads the variables of `.hit/.env` and `.hit/.env.local`, the latter taking
+/// precedence.
+pub fn load_dotenv_vars() -> Result<HashMap<String, String>, CliError> {
+    let mut vars = HashMap::new();
+    for file_name in DOTENV_FILES {
+        let path = get_config_dir().join(file_name);
+        if !path.exists() {
+            continue;
+        }
+        let to_cli_error = |e: dotenvy::Error| CliError {
+            message: format!("could not read {}: {}", path.display(), e),
+            help: None,
+        };
+        for item in dotenvy::from_path_iter(&path).map_err(to_cli_error)? {
+            let (key, value) = item.map_err(to_cli_error)?;
+            vars.insert(key, value);
+        }
+    }
+    Ok(vars)
 }
 
-pub fn list_envs() -> Vec<String> {
-    let mut envs = Config::new()
+/// Resolves the values of `env_vars`. References are resolved lazily: only the
+/// ones named in `used` are read, and their values are recorded in `secrets`.
+pub fn resolve_env_vars(
+    env_vars: &IndexMap<String, EnvValue>,
+    used: &HashSet<String>,
+    secrets: &mut Secrets,
+) -> Result<HashMap<String, String>, CliError> {
+    let mut dotenv_vars = None;
+    let mut resolved = HashMap::new();
+
+    let mut names: Vec<&String> = env_vars.keys().collect();
+    names.sort();
+    for name in names {
+        let value = &env_vars[name];
+        if !value.is_reference() {
+            resolved.insert(name.clone(), value.resolve(name, &HashMap::new())?);
+        } else if used.contains(name) {
+            if dotenv_vars.is_none() {
+                dotenv_vars = Some(load_dotenv_vars()?);
+            }
+            let value = value.resolve(name, dotenv_vars.as_ref().unwrap())?;
+            secrets.add(&value);
+            resolved.insert(name.clone(), value);
+        }
+    }
+    Ok(resolved)
+}
+
+pub const ENV_ENV_VAR: &str = "HIT_ENV";
+
+/// Returns the env activated with `hit env use`.
+pub fn get_stored_env() -> Option<String> {
+    get_app_config().get_current_env().cloned()
+}
+
+/// Returns the env to use when none is passed explicitly: `$HIT_ENV` if set,
+/// or else the stored env.
+pub fn get_env() -> Option<String> {
+    std::env::var(ENV_ENV_VAR)
+        .ok()
+        .filter(|env| !env.is_empty())
+        .or_else(get_stored_env)
+}
+
+pub fn set_env(env: String) -> Result<(), CliError> {
+    let envs = list_envs()?;
+    if !envs.contains(&env) {
+        return Err(env_not_found(&env, &envs));
+    }
+    update_app_config(|app_config| app_config.set_current_env(env));
+    Ok(())
+}
+
+pub fn list_envs() -> Result<Vec<String>, CliError> {
+    let mut envs = Config::new()?
         .envs
         .keys()
         .map(|k| k.clone())
         .collect::<Vec<String>>();
     envs.sort();
-    envs
+    Ok(envs)
+}
+
+fn env_not_found(env: &str, envs: &[String]) -> CliError {
+    CliError {
+        message: format!("env `{}` not found", env),
+        help: Some(format!("available envs: {}", envs.join(", "))),
+    }
+}
+
+fn env_already_exists(env: &str) -> CliError {
+    CliError {
+        message: format!("env `{}` already exists", env),
+        help: None,
+    }
+}
+
+/// Returns the env to operate on: the one provided, or else the active one.
+pub fn resolve_env(env: Option<String>) -> Result<String, CliError> {
+    env.or_else(get_env).ok_or_else(|| CliError {
+        message: "env not set".to_string(),
+        help: Some(format!(
+            "pass --env, set ${} or activate one with `hit env use <env>`",
+            ENV_ENV_VAR
+        )),
+    })
+}
+
+pub fn get_env_vars(env: &str) -> Result<IndexMap<String, EnvValue>, CliError> {
+    let mut config = Config::new()?;
+    match config.envs.shift_remove(env) {
+        Some(vars) => Ok(vars),
+        None => Err(env_not_found(env, &list_envs()?)),
+    }
+}
+
+pub fn add_env(env: String) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::new()?;
+    if config.envs.contains_key(&env) {
+        return Err(Box::new(env_already_exists(&env)));
+    }
+    config.envs.insert(env, IndexMap::new());
+    config.save()?;
+    Ok(())
+}
+
+pub fn remove_env(env: &str) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::new()?;
+    if config.envs.shift_remove(env).is_none() {
+        return Err(Box::new(env_not_found(env, &list_envs()?)));
+    }
+    config.tls.shift_remove(env);
+    config.network.shift_remove(env);
+    config.save()?;
+
+    update_app_config(|app_config| app_config.remove_env(env));
+    Ok(())
+}
+
+pub fn copy_env(from: &str, to: String) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::new()?;
+    if config.envs.contains_key(&to) {
+        return Err(Box::new(env_already_exists(&to)));
+    }
+    let vars = match config.envs.get(from) {
+        Some(vars) => vars.clone(),
+        None => return Err(Box::new(env_not_found(from, &list_envs()?))),
+    };
+    if let Some(tls) = config.tls.get(from).cloned() {
+        config.tls.insert(to.clone(), tls);
+    }
+    if let Some(network) = config.network.get(from).cloned() {
+        config.network.insert(to.clone(), network);
+    }
+    config.envs.insert(to, vars);
+    config.save()?;
+    Ok(())
+}
+
+pub fn rename_env(from: &str, to: String) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::new()?;
+    if config.envs.contains_key(&to) {
+        return Err(Box::new(env_already_exists(&to)));
+    }
+    let (index, _, vars) = match config.envs.shift_remove_full(from) {
+        Some(entry) => entry,
+        None => return Err(Box::new(env_not_found(from, &list_envs()?))),
+    };
+    config.envs.shift_insert(index, to.clone(), vars);
+    rename_key(&mut config.tls, from, &to);
+    rename_key(&mut config.network, from, &to);
+    config.save()?;
+
+    update_app_config(|app_config| app_config.rename_env(from, &to));
+    Ok(())
+}
+
+/// Renames the settings of an env, keeping their position.
+fn rename_key<T>(map: &mut IndexMap<String, T>, from: &str, to: &str) {
+    if let Some((index, _, value)) = map.shift_remove_full(from) {
+        map.shift_insert(index, to.to_string(), value);
+    }
+}
+
+pub fn set_env_var(env: &str, key: String, value: String) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::new()?;
+    match config.envs.get_mut(env) {
+        Some(vars) => vars.insert(key, EnvValue::Value(value)),
+        None => return Err(Box::new(env_not_found(env, &list_envs()?))),
+    };
+    config.save()?;
+    Ok(())
+}
+
+pub fn unset_env_var(env: &str, key: &str) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::new()?;
+    let vars = match config.envs.get_mut(env) {
+        Some(vars) => vars,
+        None => return Err(Box::new(env_not_found(env, &list_envs()?))),
+    };
+    if vars.shift_remove(key).is_none() {
+        return Err(Box::new(CliError {
+            message: format!("variable `{}` not found in env `{}`", key, env),
+            help: None,
+        }));
+    }
+    config.save()?;
+    Ok(())
 }
@@ -1,15 +1,122 @@
-use crate::core::app_config::get_app_config;
+use crate::core::app_config::{get_app_config, update_app_config};
+use crate::utils::error::CliError;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-pub fn get_ephenvs() -> HashMap<String, String> {
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Ephenv {
+    Value(String),
+    Expiring { value: String, expires_at: u64 },
+}
+
+impl Ephenv {
+    pub fn new(value: String, ttl: Option<Duration>) -> Ephenv {
+        match ttl {
+            Some(ttl) => Ephenv::Expiring {
+                value,
+                expires_at: now() + ttl.as_secs(),
+            },
+            None => Ephenv::Value(value),
+        }
+    }
+
+    pub fn value(&self) -> &String {
+        match self {
+            Ephenv::Value(value) => value,
+            Ephenv::Expiring { value, .. } => value,
+        }
+    }
+
+    pub fn expires_in(&self) -> Option<Duration> {
+        match self {
+            Ephenv::Value(_) => None,
+            Ephenv::Expiring { expires_at, .. } => {
+                Some(Duration::from_secs(expires_at.saturating_sub(now())))
+            }
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_in().is_some_and(|duration| duration.is_zero())
+    }
+}
+
+pub struct EphenvEntry {
+    pub key: String,
+    pub ephenv: Ephenv,
+    /// The env the ephenv is scoped to, if any
+    pub env: Option<String>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Returns every unexpired ephenv, unscoped ones first.
+pub fn list_ephenvs() -> Vec<EphenvEntry> {
     let app_config = get_app_config();
-    if let Some(ephenvs) = app_config.get_ephenvs() {
-        return ephenvs.clone();
+    let mut entries = Vec::new();
+
+    let mut scopes = app_config.get_ephenv_scopes();
+    scopes.sort();
+    for env in [None].into_iter().chain(scopes.into_iter().map(Some)) {
+        let mut scope_entries: Vec<EphenvEntry> = app_config
+            .get_ephenvs(env.as_deref())
+            .into_iter()
+            .filter(|(_, ephenv)| !ephenv.is_expired())
+            .map(|(key, ephenv)| EphenvEntry {
+                key,
+                ephenv,
+                env: env.clone(),
+            })
+            .collect();
+        scope_entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries.extend(scope_entries);
+    }
+    entries
+}
+
+/// Returns the ephenvs that apply when running commands against `env`, with
+/// the ones scoped to `env` taking precedence over unscoped ones.
+pub fn get_ephenv_entries(env: Option<&str>) -> HashMap<String, EphenvEntry> {
+    let mut entries = HashMap::new();
+    for entry in list_ephenvs() {
+        if entry.env.is_none() || entry.env.as_deref() == env {
+            entries.insert(entry.key.clone(), entry);
+        }
+    }
+    entries
+}
+
+pub fn get_ephenvs(env: Option<&str>) -> HashMap<String, String> {
+    get_ephenv_entries(env)
+        .into_iter()
+        .map(|(key, entry)| (key, entry.ephenv.value().clone()))
+        .collect()
+}
+
+pub fn set_ephenv(key: String, value: String, env: Option<String>, ttl: Option<Duration>) {
+    update_app_config(|app_config| app_config.set_ephenv(key, Ephenv::new(value, ttl), env));
+}
+
+pub fn unset_ephenv(key: &str, env: Option<&str>) -> Result<(), CliError> {
+    if update_app_config(|app_config| app_config.unset_ephenv(key, env)) {
+        return Ok(());
     }
-    HashMap::new()
+    Err(CliError {
+        message: match env {
+            Some(env) => format!("ephenv `{}` not set for env `{}`", key, env),
+            None => format!("ephenv `{}` not set", key),
+        },
+        help: None,
+    })
 }
 
-pub fn set_ephenv(key: String, value: String) -> () {
-    let mut app_config = get_app_config();
-    app_config.set_ephenv(key, value);
+pub fn clear_ephenvs(env: Option<&str>) {
+    update_app_config(|app_config| app_config.clear_ephenvs(env));
 }
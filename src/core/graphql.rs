@@ -0,0 +1,484 @@
+//! GraphQL commands send a query document along with its variables, which are
+//! provided as flags when running the command.
+
+use crate::core::command::{BodyType, Command};
+use crate::core::config::{CommandType, Config};
+use crate::utils::error::CliError;
+use crate::utils::headers::Headers;
+use crate::utils::http::HttpMethod;
+use convert_case::{Case, Casing};
+use graphql_parser::query::{self, OperationDefinition};
+use graphql_parser::schema::{self, TypeDefinition, TypeExtension};
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+
+const BUILTIN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+
+/// A variable declared by an operation of a query document.
+#[derive(Debug, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    /// The type as written in the document, e.g. `[ID!]!`
+    pub type_name: String,
+    /// Whether the variable is non-null without a default value
+    pub required: bool,
+}
+
+impl Variable {
+    /// Converts a value given on the command line. `String` and `ID` values
+    /// are sent as is, others are parsed as JSON when possible, so that enum
+    /// values and custom scalars can be passed without quotes.
+    pub fn value(&self, value: &str) -> Value {
+        match self.type_name.trim_end_matches('!') {
+            "String" | "ID" => Value::String(value.to_string()),
+            _ => serde_json::from_str(value).unwrap_or(Value::String(value.to_string())),
+        }
+    }
+}
+
+pub fn parse_variables(document: &str) -> Result<Vec<Variable>, CliError> {
+    let document = query::parse_query::<String>(document).map_err(|e| CliError {
+        message: format!("invalid GraphQL query: {}", e.to_string().trim_end()),
+        help: None,
+    })?;
+
+    let mut variables: Vec<Variable> = Vec::new();
+    for definition in document.definitions {
+        let definitions = match definition {
+            query::Definition::Operation(OperationDefinition::Query(query)) => {
+                query.variable_definitions
+            }
+            query::Definition::Operation(OperationDefinition::Mutation(mutation)) => {
+                mutation.variable_definitions
+            }
+            query::Definition::Operation(OperationDefinition::Subscription(subscription)) => {
+                subscription.variable_definitions
+            }
+            _ => continue,
+        };
+        for definition in definitions {
+            if variables.iter().any(|v| v.name == definition.name) {
+                continue;
+            }
+            variables.push(Variable {
+                required: matches!(definition.var_type, query::Type::NonNullType(_))
+                    && definition.default_value.is_none(),
+                type_name: definition.var_type.to_string(),
+                name: definition.name,
+            });
+        }
+    }
+    Ok(variables)
+}
+
+/// Returns the errors of a GraphQL response, along with the path of the field
+/// they were raised for.
+pub fn response_errors(response: &Value) -> Vec<String> {
+    let Some(errors) = response.get("errors").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    errors
+        .iter()
+        .map(|error| {
+            let message = match error.get("message") {
+                Some(Value::String(message)) => message.clone(),
+                _ => error.to_string(),
+            };
+            match error.get("path").and_then(Value::as_array) {
+                Some(path) => format!(
+                    "{} (at {})",
+                    message,
+                    path.iter()
+                        .map(|segment| match segment {
+                            Value::String(segment) => segment.clone(),
+                            segment => segment.to_string(),
+                        })
+                        .collect::<Vec<String>>()
+                        .join(".")
+                ),
+                None => message,
+            }
+        })
+        .collect()
+}
+
+struct Field {
+    name: String,
+    /// The name, type and default value of each argument
+    arguments: Vec<(String, String, Option<String>)>,
+    type_name: String,
+}
+
+/// The parts of a schema needed to generate commands, read from either SDL or
+/// the result of an introspection query.
+struct Schema {
+    query_type: String,
+    mutation_type: Option<String>,
+    /// The fields of object and interface types
+    fields: HashMap<String, Vec<Field>>,
+    /// Scalar and enum types
+    leaf_types: HashSet<String>,
+}
+
+fn named_type(type_name: &str) -> &str {
+    type_name.trim_matches(|c| matches!(c, '[' | ']' | '!'))
+}
+
+impl Schema {
+    fn from_sdl(sdl: &str) -> Result<Schema, CliError> {
+        let document = schema::parse_schema::<String>(sdl).map_err(|e| CliError {
+            message: format!("invalid GraphQL schema: {}", e.to_string().trim_end()),
+            help: None,
+        })?;
+
+        let mut schema = Schema {
+            query_type: "Query".to_string(),
+            mutation_type: Some("Mutation".to_string()),
+            fields: HashMap::new(),
+            leaf_types: HashSet::new(),
+        };
+        for definition in document.definitions {
+            let (name, fields) = match definition {
+                schema::Definition::SchemaDefinition(definition) => {
+                    if let Some(query) = definition.query {
+                        schema.query_type = query;
+                    }
+                    schema.mutation_type = definition.mutation;
+                    continue;
+                }
+                schema::Definition::TypeDefinition(TypeDefinition::Object(object)) => {
+                    (object.name, object.fields)
+                }
+                schema::Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                    (interface.name, interface.fields)
+                }
+                schema::Definition::TypeExtension(TypeExtension::Object(object)) => {
+                    (object.name, object.fields)
+                }
+                schema::Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => {
+                    schema.leaf_types.insert(scalar.name);
+                    continue;
+                }
+                schema::Definition::TypeDefinition(TypeDefinition::Enum(enum_type)) => {
+                    schema.leaf_types.insert(enum_type.name);
+                    continue;
+                }
+                _ => continue,
+            };
+            schema
+                .fields
+                .entry(name)
+                .or_default()
+                .extend(fields.into_iter().map(|field| {
+                    Field {
+                        name: field.name,
+                        arguments: field
+                            .arguments
+                            .into_iter()
+                            .map(|argument| {
+                                (
+                                    argument.name,
+                                    argument.value_type.to_string(),
+                                    argument.default_value.map(|value| value.to_string()),
+                                )
+                            })
+                            .collect(),
+                        type_name: field.field_type.to_string(),
+                    }
+                }));
+        }
+        Ok(schema)
+    }
+
+    fn from_introspection(json: &Value) -> Result<Schema, CliError> {
+        let introspection = json
+            .pointer("/data/__schema")
+            .or_else(|| json.get("__schema"))
+            .ok_or_else(|| CliError {
+                message: "the file is not the result of an introspection query".to_string(),
+                help: Some("expected a JSON object with a `__schema` field".to_string()),
+            })?;
+
+        fn type_ref(json: &Value) -> String {
+            match json["kind"].as_str() {
+                Some("NON_NULL") => format!("{}!", type_ref(&json["ofType"])),
+                Some("LIST") => format!("[{}]", type_ref(&json["ofType"])),
+                _ => json["name"].as_str().unwrap_or_default().to_string(),
+            }
+        }
+        let array = |json: &Value| json.as_array().cloned().unwrap_or_default();
+
+        let mut schema = Schema {
+            query_type: introspection["queryType"]["name"]
+                .as_str()
+                .unwrap_or("Query")
+                .to_string(),
+            mutation_type: introspection["mutationType"]["name"]
+                .as_str()
+                .map(str::to_string),
+            fields: HashMap::new(),
+            leaf_types: HashSet::new(),
+        };
+        for type_json in array(&introspection["types"]) {
+            let name = type_json["name"].as_str().unwrap_or_default().to_string();
+            match type_json["kind"].as_str() {
+                Some("SCALAR" | "ENUM") => {
+                    schema.leaf_types.insert(name);
+                }
+                Some("OBJECT" | "INTERFACE") => {
+                    let fields = array(&type_json["fields"])
+                        .iter()
+                        .map(|field| Field {
+                            name: field["name"].as_str().unwrap_or_default().to_string(),
+                            arguments: array(&field["args"])
+                                .iter()
+                                .map(|argument| {
+                                    (
+                                        argument["name"].as_str().unwrap_or_default().to_string(),
+                                        type_ref(&argument["type"]),
+                                        argument["defaultValue"].as_str().map(str::to_string),
+                                    )
+                                })
+                                .collect(),
+                            type_name: type_ref(&field["type"]),
+                        })
+                        .collect();
+                    schema.fields.insert(name, fields);
+                }
+                _ => {}
+            }
+        }
+        Ok(schema)
+    }
+
+    fn is_leaf(&self, type_name: &str) -> bool {
+        let name = named_type(type_name);
+        BUILTIN_SCALARS.contains(&name) || self.leaf_types.contains(name)
+    }
+
+    /// Selects the leaf fields of `type_name` that take no required argument,
+    /// or only `__typename` when there are none.
+    fn selection(&self, type_name: &str) -> Option<Vec<String>> {
+        if self.is_leaf(type_name) {
+            return None;
+        }
+        let selection: Vec<String> =
+            self.fields
+                .get(named_type(type_name))
+                .into_iter()
+                .flatten()
+                .filter(|field| self.is_leaf(&field.type_name))
+                .filter(|field| {
+                    field.arguments.iter().all(|(_, type_name, default)| {
+                        !type_name.ends_with('!') || default.is_some()
+                    })
+                })
+                .map(|field| field.name.clone())
+                .collect();
+        match selection.is_empty() {
+            true => Some(vec!["__typename".to_string()]),
+            false => Some(selection),
+        }
+    }
+
+    fn document(&self, operation: &str, field: &Field) -> String {
+        let mut document = format!("{} {}", operation, field.name.to_case(Case::Pascal));
+        let mut call = field.name.clone();
+        if !field.arguments.is_empty() {
+            let declarations: Vec<String> = field
+                .arguments
+                .iter()
+                .map(|(name, type_name, default)| match default {
+                    Some(default) => format!("${}: {} = {}", name, type_name, default),
+                    None => format!("${}: {}", name, type_name),
+                })
+                .collect();
+            let arguments: Vec<String> = field
+                .arguments
+                .iter()
+                .map(|(name, _, _)| format!("{}: ${}", name, name))
+                .collect();
+            document.push_str(&format!("({})", declarations.join(", ")));
+            call.push_str(&format!("({})", arguments.join(", ")));
+        }
+        document.push_str(&format!(" {{\n  {}", call));
+        if let Some(selection) = self.selection(&field.type_name) {
+            document.push_str(" {\n");
+            for name in selection {
+                document.push_str(&format!("    {}\n", name));
+            }
+            document.push_str("  }");
+        }
+        document.push_str("\n}\n");
+        document
+    }
+}
+
+pub fn generate_config_from_file(path: &str) -> Result<Config, Box<dyn Error>> {
+    let content = fs::read_to_string(path).map_err(|e| CliError {
+        message: format!("could not read {}: {}", path, e),
+        help: None,
+    })?;
+    let schema = match serde_json::from_str::<Value>(&content) {
+        Ok(json) => Schema::from_introspection(&json)?,
+        Err(_) => Schema::from_sdl(&content)?,
+    };
+    Ok(generate_config(&schema))
+}
+
+/// Generates a `query` and a `mutation` command group, with a command per
+/// field of the root types.
+fn generate_config(schema: &Schema) -> Config {
+    let mut config = Config::empty();
+    config.envs.insert(
+        "prod".to_string(),
+        IndexMap::from([("API_URL".to_string(), String::new().into())]),
+    );
+
+    for (operation, type_name) in [
+        ("query", Some(&schema.query_type)),
+        ("mutation", schema.mutation_type.as_ref()),
+    ] {
+        let Some(fields) = type_name.and_then(|name| schema.fields.get(name)) else {
+            continue;
+        };
+        let commands: IndexMap<String, Box<CommandType>> = fields
+            .iter()
+            .map(|field| {
+                let command = Command {
+                    method: HttpMethod::POST,
+                    url: "{{API_URL}}".to_string(),
+                    headers: Headers::new(),
+                    body_type: BodyType::Json,
+                    body: None,
+                    graphql: Some(schema.document(operation, field)),
+                    grpc: None,
+                    tls: None,
+                    cookies: None,
+                    stream: false,
+                    postscript: None,
+                    extra: IndexMap::new(),
+                };
+                (
+                    field.name.to_case(Case::Kebab),
+                    Box::new(CommandType::Command(Box::new(command))),
+                )
+            })
+            .collect();
+        if !commands.is_empty() {
+            config.commands.insert(
+                operation.to_string(),
+                Box::new(CommandType::NestedCommand(commands)),
+            );
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_parse_variables() {
+        let variables = parse_variables(
+            "query Users($first: Int = 10, $ids: [ID!]!, $name: String) { users { id } }",
+        )
+        .unwrap();
+        assert_eq!(
+            variables,
+            [
+                Variable {
+                    name: "first".to_string(),
+                    type_name: "Int".to_string(),
+                    required: false,
+                },
+                Variable {
+                    name: "ids".to_string(),
+                    type_name: "[ID!]!".to_string(),
+                    required: true,
+                },
+                Variable {
+                    name: "name".to_string(),
+                    type_name: "String".to_string(),
+                    required: false,
+                },
+            ]
+        );
+        assert!(parse_variables("query {").is_err());
+    }
+
+    #[rstest]
+    #[case("String!", "42", json!("42"))]
+    #[case("ID", "42", json!("42"))]
+    #[case("Int!", "42", json!(42))]
+    #[case("[Int]", "[1, 2]", json!([1, 2]))]
+    #[case("Status", "ACTIVE", json!("ACTIVE"))]
+    fn test_variable_value(#[case] type_name: &str, #[case] value: &str, #[case] expected: Value) {
+        let variable = Variable {
+            name: "v".to_string(),
+            type_name: type_name.to_string(),
+            required: false,
+        };
+        assert_eq!(variable.value(value), expected);
+    }
+
+    #[rstest]
+    fn test_response_errors() {
+        let response = json!({
+            "data": null,
+            "errors": [
+                { "message": "not found", "path": ["users", 0, "name"] },
+                { "message": "rate limited" }
+            ]
+        });
+        assert_eq!(
+            response_errors(&response),
+            ["not found (at users.0.name)", "rate limited"]
+        );
+    }
+
+    #[rstest]
+    fn test_generate_config_from_sdl() {
+        let schema = Schema::from_sdl(
+            "type Query { user(id: ID!): User, status: Status }
+             type Mutation { rename(id: ID!, name: String = \"x\"): User! }
+             type User { id: ID! name: String friends(first: Int!): [User] }
+             enum Status { UP DOWN }",
+        )
+        .unwrap();
+        let config = generate_config(&schema);
+        let CommandType::NestedCommand(queries) = &*config.commands["query"] else {
+            panic!("expected a query command group");
+        };
+        let CommandType::Command(user) = &*queries["user"] else {
+            panic!("expected a command");
+        };
+        assert_eq!(
+            user.graphql.as_deref(),
+            Some("query User($id: ID!) {\n  user(id: $id) {\n    id\n    name\n  }\n}\n")
+        );
+        let CommandType::Command(status) = &*queries["status"] else {
+            panic!("expected a command");
+        };
+        assert_eq!(
+            status.graphql.as_deref(),
+            Some("query Status {\n  status\n}\n")
+        );
+        let CommandType::NestedCommand(mutations) = &*config.commands["mutation"] else {
+            panic!("expected a mutation command group");
+        };
+        let CommandType::Command(rename) = &*mutations["rename"] else {
+            panic!("expected a command");
+        };
+        assert_eq!(
+            rename.graphql.as_deref(),
+            Some("mutation Rename($id: ID!, $name: String = \"x\") {\n  rename(id: $id, name: $name) {\n    id\n    name\n  }\n}\n")
+        );
+    }
+}
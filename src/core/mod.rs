@@ -1,6 +1,11 @@
 pub mod app_config;
 pub mod command;
 pub mod config;
+pub mod cookies;
 pub mod env;
 pub mod ephenv;
+pub mod graphql;
 pub mod openapi;
+pub mod params;
+pub mod project;
+pub mod template;
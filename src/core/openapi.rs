@@ -1,19 +1,31 @@
-use crate::core::command::Command;
+use crate::core::command::{BodyType, Command};
 use crate::core::config::{CommandType, Config};
+use crate::utils::error::CliError;
+use crate::utils::headers::Headers;
 use crate::utils::http::HttpMethod;
 use convert_case::{Case, Casing};
+use indexmap::IndexMap;
 use openapiv3::{
     OpenAPI, Operation, Parameter, PathItem, ReferenceOr, RequestBody, Schema, SchemaKind, Type,
 };
 use serde_json::{json, Value};
-use std::collections::HashMap;
 use std::error::Error;
+use std::fs;
+
+pub fn generate_config_from_file(path: &str) -> Result<Config, Box<dyn Error>> {
+    let spec_content = fs::read_to_string(path).map_err(|e| CliError {
+        message: format!("could not read {}: {}", path, e),
+        help: None,
+    })?;
+    let spec: OpenAPI = serde_yaml::from_str(&spec_content).map_err(|e| CliError {
+        message: format!("could not parse {} as an OpenAPI spec: {}", path, e),
+        help: None,
+    })?;
+    generate_config(&spec)
+}
 
 pub fn generate_config(spec: &OpenAPI) -> Result<Config, Box<dyn Error>> {
-    let mut config = Config {
-        envs: HashMap::new(),
-        commands: HashMap::new(),
-    };
+    let mut config = Config::empty();
     // Extract server URL
     let api_url = if let Some(server) = spec.servers.first() {
         server.url.clone()
@@ -24,11 +36,12 @@ pub fn generate_config(spec: &OpenAPI) -> Result<Config, Box<dyn Error>> {
     // Create environment configuration
     config.envs.insert(
         "prod".to_string(),
-        HashMap::from([("API_URL".to_string(), api_url.to_string())]),
+        IndexMap::from([("API_URL".to_string(), api_url.into())]),
     );
 
     // Group operations by tag
-    let mut tag_operations: HashMap<String, Vec<(&String, &PathItem, Operation)>> = HashMap::new();
+    let mut tag_operations: IndexMap<String, Vec<(&String, &PathItem, Operation)>> =
+        IndexMap::new();
 
     // Process paths
     for (path, path_item) in spec.paths.iter() {
@@ -77,7 +90,7 @@ pub fn generate_config(spec: &OpenAPI) -> Result<Config, Box<dyn Error>> {
 
     // Convert grouped operations to commands
     for (tag, operations) in tag_operations {
-        let mut tag_commands = HashMap::new();
+        let mut tag_commands = IndexMap::new();
 
         for (path, _path_item, operation) in operations {
             // Derive command name from operationId or path
@@ -95,10 +108,8 @@ pub fn generate_config(spec: &OpenAPI) -> Result<Config, Box<dyn Error>> {
 
             tag_commands.insert(
                 command_name,
-                Box::new(CommandType::Command(create_command_for_operation(
-                    path,
-                    &operation,
-                    &spec.components,
+                Box::new(CommandType::Command(Box::new(
+                    create_command_for_operation(path, &operation, &spec.components),
                 ))),
             );
         }
@@ -116,7 +127,7 @@ pub fn generate_config(spec: &OpenAPI) -> Result<Config, Box<dyn Error>> {
 }
 
 fn process_operation<'a>(
-    tag_operations: &mut HashMap<String, Vec<(&'a String, &'a PathItem, Operation)>>,
+    tag_operations: &mut IndexMap<String, Vec<(&'a String, &'a PathItem, Operation)>>,
     path: &'a String,
     path_item: &'a PathItem,
     operation_opt: &'a Option<Operation>,
@@ -190,18 +201,26 @@ fn create_command_for_operation(
     let url = process_path_and_query(path, &operation.parameters);
 
     // Process request body if present
-    let body = if let (Some(req_body), Some(components)) = (&operation.request_body, &components) {
-        extract_request_body(req_body, components)
-    } else {
-        None
-    };
+    let (body_type, body) =
+        if let (Some(req_body), Some(components)) = (&operation.request_body, &components) {
+            extract_request_body(req_body, components)
+        } else {
+            (BodyType::Json, None)
+        };
 
     Command {
         method,
         url: format!("{{{{API_URL}}}}{}", url),
+        body_type,
         body,
+        graphql: None,
+        grpc: None,
+        tls: None,
+        cookies: None,
+        stream: false,
         postscript: None,
-        headers: HashMap::new(),
+        extra: IndexMap::new(),
+        headers: Headers::new(),
     }
 }
 
@@ -234,14 +253,20 @@ fn process_path_and_query(path: &str, parameters: &[ReferenceOr<Parameter>]) ->
 fn extract_request_body(
     request_body: &ReferenceOr<RequestBody>,
     components: &openapiv3::Components,
-) -> Option<Value> {
+) -> (BodyType, Option<Value>) {
     match request_body {
         ReferenceOr::Item(body) => {
-            // Try to get JSON schema
-            if let Some(json_content) = body.content.get("application/json") {
-                return extract_schema(&json_content.schema, components);
+            // Try to get JSON schema, then form schemas
+            for (content_type, body_type) in [
+                ("application/json", BodyType::Json),
+                ("application/x-www-form-urlencoded", BodyType::Form),
+                ("multipart/form-data", BodyType::Multipart),
+            ] {
+                if let Some(content) = body.content.get(content_type) {
+                    return (body_type, extract_schema(&content.schema, components));
+                }
             }
-            None
+            (BodyType::Json, None)
         }
         ReferenceOr::Reference { reference } => {
             // Handle reference to component
@@ -255,7 +280,7 @@ fn extract_request_body(
                     return extract_request_body(ref_body, components);
                 }
             }
-            None
+            (BodyType::Json, None)
         }
     }
 }
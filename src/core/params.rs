@@ -0,0 +1,322 @@
+//! Params are written as `:name` in the url and body of a command, where the
+//! name starts with a letter or an underscore. `::name` is the literal text
+//! `:name`, while other double colons are left as they are.
+
+use indexmap::IndexSet;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// NOTE: placeholders use private use characters, which neither templates nor
+// urls are expected to contain
+const PLACEHOLDER_START: char = '\u{E000}';
+const PLACEHOLDER_END: char = '\u{E001}';
+
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+const QUERY_COMPONENT: &AsciiSet = &PATH_SEGMENT.add(b'&').add(b'+').add(b'=');
+
+enum Token<'a> {
+    Text(&'a str),
+    Param(&'a str),
+}
+
+fn is_name_start(char: char) -> bool {
+    char.is_ascii_alphabetic() || char == '_'
+}
+
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut chars = input.char_indices().peekable();
+    while let Some((index, char)) = chars.next() {
+        if char != ':' {
+            continue;
+        }
+        match chars.peek() {
+            Some((_, ':')) if input[index + 2..].starts_with(is_name_start) => {
+                // NOTE: keep the first colon of `::name` as text, and drop the second
+                tokens.push(Token::Text(&input[text_start..index + 1]));
+                chars.next();
+                text_start = index + 2;
+            }
+            Some((_, next)) if is_name_start(*next) => {
+                let name_start = index + 1;
+                let mut name_end = input.len();
+                while let Some((next_index, next)) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || *next == '_') {
+                        name_end = *next_index;
+                        break;
+                    }
+                    chars.next();
+                }
+                tokens.push(Token::Text(&input[text_start..index]));
+                tokens.push(Token::Param(&input[name_start..name_end]));
+                text_start = name_end;
+            }
+            _ => {}
+        }
+    }
+    tokens.push(Token::Text(&input[text_start..]));
+    tokens
+}
+
+/// Returns the names of the params in `input`, in order of appearance.
+pub fn get_params(input: &str) -> IndexSet<String> {
+    tokenize(input)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Param(name) => Some(name.to_string()),
+            Token::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Whether `input` starts with a param, whose value then makes up its start.
+pub fn starts_with_param(input: &str) -> bool {
+    matches!(
+        tokenize(input).as_slice(),
+        [Token::Text(""), Token::Param(_), ..]
+    )
+}
+
+/// Returns the names of the params in the keys and string values of `value`.
+pub fn get_json_params(value: &Value) -> IndexSet<String> {
+    match value {
+        Value::String(string) => get_params(string),
+        Value::Array(values) => values.iter().flat_map(get_json_params).collect(),
+        Value::Object(map) => map
+            .iter()
+            .flat_map(|(key, value)| get_params(key).into_iter().chain(get_json_params(value)))
+            .collect(),
+        _ => IndexSet::new(),
+    }
+}
+
+/// Replaces the params with values by placeholders, so that the rest of
+/// `input` can be rendered without touching the values. Params without a value
+/// are kept as they are.
+fn with_placeholders<'a>(
+    input: &str,
+    values: &'a HashMap<String, String>,
+) -> (String, Vec<&'a String>) {
+    let mut text = String::new();
+    let mut placeholder_values = Vec::new();
+    for token in tokenize(input) {
+        match token {
+            Token::Text(string) => text.push_str(string),
+            Token::Param(name) => match values.get(name) {
+                Some(value) => {
+                    text.push(PLACEHOLDER_START);
+                    text.push_str(&placeholder_values.len().to_string());
+                    text.push(PLACEHOLDER_END);
+                    placeholder_values.push(value);
+                }
+                None => {
+                    text.push(':');
+                    text.push_str(name);
+                }
+            },
+        }
+    }
+    (text, placeholder_values)
+}
+
+/// Replaces the placeholders of `text` by their values, passing each through
+/// `encode` along with the index of the placeholder in `text`.
+fn fill_placeholders(
+    text: &str,
+    values: &[&String],
+    encode: impl Fn(&str, usize) -> String,
+) -> String {
+    let mut filled = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        let Some(length) = rest[start..].find(PLACEHOLDER_END) else {
+            break;
+        };
+        let index = &rest[start + PLACEHOLDER_START.len_utf8()..start + length];
+        let Some(value) = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| values.get(index))
+        else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        filled.push_str(&encode(value, text.len() - rest.len() + start));
+        rest = &rest[start + length + PLACEHOLDER_END.len_utf8()..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Substitutes the params of `input` with their values, and renders the rest
+/// of it with `render`.
+pub fn substitute<E>(
+    input: &str,
+    values: &HashMap<String, String>,
+    render: impl Fn(&str) -> Result<String, E>,
+) -> Result<String, E> {
+    let (text, placeholder_values) = with_placeholders(input, values);
+    let rendered = render(&text)?;
+    Ok(fill_placeholders(
+        &rendered,
+        &placeholder_values,
+        |value, _| value.to_string(),
+    ))
+}
+
+/// Like `substitute`, with the values percent-encoded as path segments or, in
+/// the query string, as query components.
+pub fn substitute_url<E>(
+    url: &str,
+    values: &HashMap<String, String>,
+    render: impl Fn(&str) -> Result<String, E>,
+) -> Result<String, E> {
+    let (text, placeholder_values) = with_placeholders(url, values);
+    let rendered = render(&text)?;
+    let query_start = rendered.find('?').unwrap_or(rendered.len());
+    Ok(fill_placeholders(
+        &rendered,
+        &placeholder_values,
+        |value, index| {
+            let encode_set = if index < query_start {
+                PATH_SEGMENT
+            } else {
+                QUERY_COMPONENT
+            };
+            utf8_percent_encode(value, encode_set).to_string()
+        },
+    ))
+}
+
+/// Like `substitute`, for each key and string value of `value`. Since values
+/// are substituted in place, the JSON stays valid whatever they contain.
+pub fn substitute_json<E>(
+    value: &Value,
+    values: &HashMap<String, String>,
+    render: &impl Fn(&str) -> Result<String, E>,
+) -> Result<Value, E> {
+    Ok(match value {
+        Value::String(string) => Value::String(substitute(string, values, render)?),
+        Value::Array(array) => Value::Array(
+            array
+                .iter()
+                .map(|value| substitute_json(value, values, render))
+                .collect::<Result<_, E>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    Ok((
+                        substitute(key, values, render)?,
+                        substitute_json(value, values, render)?,
+                    ))
+                })
+                .collect::<Result<Map<String, Value>, E>>()?,
+        ),
+        _ => value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+    use std::convert::Infallible;
+
+    fn as_is(input: &str) -> Result<String, Infallible> {
+        Ok(input.to_string())
+    }
+
+    fn values() -> HashMap<String, String> {
+        HashMap::from([
+            ("id".to_string(), "42".to_string()),
+            ("idType".to_string(), "uuid".to_string()),
+            ("name".to_string(), "a \"b\" :id/c&d".to_string()),
+        ])
+    }
+
+    #[rstest]
+    #[case("/users/:id/:idType", &["id", "idType"])]
+    #[case("http://localhost:8080/users/:id", &["id"])]
+    #[case("/time/12::30/:_at", &["_at"])]
+    #[case("http://[::1]:8080/items/:id", &["id"])]
+    #[case("a::b", &[])]
+    #[case("/users/:id/friends/:id", &["id"])]
+    fn test_get_params(#[case] input: &str, #[case] expected: &[&str]) {
+        assert_eq!(get_params(input).into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[rstest]
+    #[case(":file", true)]
+    #[case(":file/a.png", true)]
+    #[case("./:file", false)]
+    #[case("::file", false)]
+    #[case("a.png", false)]
+    fn test_starts_with_param(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(starts_with_param(input), expected);
+    }
+
+    #[rstest]
+    #[case(":id-:idType", "42-uuid")]
+    #[case(":name", "a \"b\" :id/c&d")]
+    #[case("a::b :missing", "a:b :missing")]
+    #[case("12::30 std::io", "12::30 std:io")]
+    #[case("http://[::1]:8080/:id", "http://[::1]:8080/42")]
+    #[case("a:::id", "a::id")]
+    fn test_substitute(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(substitute(input, &values(), as_is).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn test_substitute_url() {
+        assert_eq!(
+            substitute_url(
+                "http://localhost:8080/:name?q=:name&id=:id",
+                &values(),
+                as_is
+            )
+            .unwrap(),
+            "http://localhost:8080/a%20%22b%22%20:id%2Fc&d?q=a%20%22b%22%20:id%2Fc%26d&id=42"
+        );
+    }
+
+    #[rstest]
+    fn test_substitute_keeps_values_from_render() {
+        let render = |input: &str| Ok::<_, Infallible>(input.replace("{{X}}", ":id"));
+        assert_eq!(
+            substitute("{{X}} :id", &values(), render).unwrap(),
+            ":id 42"
+        );
+    }
+
+    #[rstest]
+    fn test_substitute_json() {
+        let body = json!({
+            "name": ":name",
+            "ids": [":id", 7],
+            ":idType": true,
+        });
+        assert_eq!(
+            substitute_json(&body, &values(), &as_is).unwrap(),
+            json!({
+                "name": "a \"b\" :id/c&d",
+                "ids": ["42", 7],
+                "uuid": true,
+            })
+        );
+    }
+}
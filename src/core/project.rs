@@ -0,0 +1,93 @@
+use crate::constants::{CONFIG_DIR, CONFIG_FILE, POSTSCRIPTS_DIR};
+use crate::core::config::Config;
+use crate::utils::error::CliError;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const PROJECT_DIR_ENV_VAR: &str = "HIT_PROJECT_DIR";
+
+static PROJECT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Overrides the project root for the rest of the invocation, as done by the
+/// `--project-dir` flag.
+pub fn set_project_dir(dir: PathBuf) {
+    let _ = PROJECT_DIR.set(absolute(dir));
+}
+
+fn absolute(dir: PathBuf) -> PathBuf {
+    let dir = env::current_dir().unwrap().join(dir);
+    dir.canonicalize().unwrap_or(dir)
+}
+
+/// Returns the nearest directory, starting from `start` and walking up, that
+/// contains a `.hit` directory.
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(CONFIG_DIR).is_dir())
+        .map(|dir| dir.to_path_buf())
+}
+
+fn get_project_dir_override() -> Option<PathBuf> {
+    if let Some(dir) = PROJECT_DIR.get() {
+        return Some(dir.clone());
+    }
+    env::var_os(PROJECT_DIR_ENV_VAR)
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| absolute(PathBuf::from(dir)))
+}
+
+/// Returns the root of the current project, i.e. the directory containing
+/// `.hit`. Falls back to the current directory when no project is found.
+pub fn get_project_root() -> PathBuf {
+    if let Some(dir) = get_project_dir_override() {
+        return dir;
+    }
+
+    let current_dir = env::current_dir().unwrap();
+    find_project_root(&current_dir).unwrap_or(current_dir)
+}
+
+/// Creates a project with the given config in the current directory, or the
+/// overridden project directory.
+pub fn init_project(config: &Config) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = get_project_dir_override().unwrap_or_else(|| env::current_dir().unwrap());
+    let config_dir = dir.join(CONFIG_DIR);
+    if config_dir.join(CONFIG_FILE).exists() {
+        return Err(Box::new(CliError {
+            message: format!("a hit project already exists in {}", dir.display()),
+            help: None,
+        }));
+    }
+
+    fs::create_dir_all(config_dir.join(POSTSCRIPTS_DIR))?;
+    set_project_dir(dir.clone());
+    config.save()?;
+    Ok(dir)
+}
+
+pub fn get_config_dir() -> PathBuf {
+    get_project_root().join(CONFIG_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use tempfile::TempDir;
+
+    #[rstest]
+    fn test_find_project_root_from_subdirectory() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        let sub_dir = project_dir.join("src").join("nested");
+        fs::create_dir_all(project_dir.join(CONFIG_DIR)).unwrap();
+        fs::create_dir_all(&sub_dir).unwrap();
+
+        assert_eq!(find_project_root(&sub_dir), Some(project_dir.clone()));
+        assert_eq!(find_project_root(&project_dir), Some(project_dir));
+    }
+}
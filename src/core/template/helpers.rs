@@ -0,0 +1,216 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+type HelperFn = fn(&[Value], &mut StdRng) -> Result<String, String>;
+
+pub const HELPERS: &[(&str, HelperFn)] = &[
+    ("uuid", uuid),
+    ("now", now),
+    ("timestamp", timestamp),
+    ("random_int", random_int),
+    ("random_string", random_string),
+    ("base64", base64),
+    ("urlencode", urlencode),
+    ("sha256", sha256),
+    ("hmac_sha256", hmac_sha256),
+    ("env", env),
+    ("json", json),
+    ("first_name", first_name),
+    ("last_name", last_name),
+    ("name", name),
+    ("email", email),
+];
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Alan", "Barbara", "Dennis", "Edsger", "Frances", "Grace", "Guido", "Hedy", "John",
+    "Ken", "Linus", "Margaret", "Radia", "Tim", "Yukihiro",
+];
+const LAST_NAMES: &[&str] = &[
+    "Allen",
+    "Berners-Lee",
+    "Dijkstra",
+    "Hamilton",
+    "Hopper",
+    "Lamarr",
+    "Liskov",
+    "Lovelace",
+    "Matsumoto",
+    "McCarthy",
+    "Perlman",
+    "Ritchie",
+    "Rossum",
+    "Thompson",
+    "Torvalds",
+    "Turing",
+];
+
+/// Wraps a helper function so that it can be registered with handlebars, and
+/// shares the random number generator between the helpers of a renderer.
+pub struct Function {
+    pub function: HelperFn,
+    pub rng: Arc<Mutex<StdRng>>,
+    pub env_values: Arc<Mutex<Vec<String>>>,
+}
+
+impl HelperDef for Function {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        helper: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        context: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        // NOTE: without arguments, a defined variable with the name of the
+        // helper takes precedence over it
+        if helper.params().is_empty() && helper.hash().is_empty() {
+            if let Some(value) = context.data().get(helper.name()) {
+                return Ok(ScopedJson::Derived(value.clone()));
+            }
+        }
+        let params: Vec<Value> = helper
+            .params()
+            .iter()
+            .map(|param| param.value().clone())
+            .collect();
+        let mut rng = self.rng.lock().unwrap();
+        let value = (self.function)(&params, &mut rng)
+            .map_err(|e| RenderErrorReason::Other(format!("`{}`: {}", helper.name(), e)))?;
+        if helper.name() == "env" {
+            self.env_values.lock().unwrap().push(value.clone());
+        }
+        Ok(ScopedJson::Derived(Value::String(value)))
+    }
+}
+
+fn string_param(params: &[Value], index: usize) -> Result<String, String> {
+    match params.get(index) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        Some(Value::Null) | None => Err(format!("expected a value as argument {}", index + 1)),
+        Some(value) => Ok(value.to_string()),
+    }
+}
+
+fn optional_int_param(params: &[Value], index: usize, default: i64) -> Result<i64, String> {
+    match params.get(index) {
+        None => Ok(default),
+        Some(value) => value
+            .as_i64()
+            .ok_or(format!("expected a number as argument {}", index + 1)),
+    }
+}
+
+fn uuid(_: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    Ok(uuid::Builder::from_random_bytes(rng.gen())
+        .into_uuid()
+        .to_string())
+}
+
+fn now(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    let now = Utc::now();
+    match params.first() {
+        None => Ok(now.to_rfc3339()),
+        Some(_) => {
+            let format = string_param(params, 0)?;
+            let mut formatted = String::new();
+            write!(formatted, "{}", now.format(&format))
+                .map_err(|_| format!("invalid format `{}`", format))?;
+            Ok(formatted)
+        }
+    }
+}
+
+fn timestamp(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    let now = Utc::now();
+    match params.first().and_then(Value::as_str) {
+        None | Some("s") => Ok(now.timestamp().to_string()),
+        Some("ms") => Ok(now.timestamp_millis().to_string()),
+        Some(unit) => Err(format!("unknown unit `{}`, expected `s` or `ms`", unit)),
+    }
+}
+
+fn random_int(params: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    let min = optional_int_param(params, 0, 0)?;
+    let max = optional_int_param(params, 1, 1000)?;
+    if min > max {
+        return Err(format!("{} is greater than {}", min, max));
+    }
+    Ok(rng.gen_range(min..=max).to_string())
+}
+
+fn random_string(params: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    let length = optional_int_param(params, 0, 16)?;
+    Ok((0..length)
+        .map(|_| rng.sample(Alphanumeric) as char)
+        .collect())
+}
+
+fn base64(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    Ok(STANDARD.encode(string_param(params, 0)?))
+}
+
+fn urlencode(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    Ok(utf8_percent_encode(&string_param(params, 0)?, NON_ALPHANUMERIC).to_string())
+}
+
+fn sha256(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    Ok(hex::encode(Sha256::digest(string_param(params, 0)?)))
+}
+
+fn hmac_sha256(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(string_param(params, 0)?.as_bytes())
+        .map_err(|e| e.to_string())?;
+    mac.update(string_param(params, 1)?.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+fn env(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    let name = string_param(params, 0)?;
+    std::env::var(&name).map_err(|_| format!("${} is not set", name))
+}
+
+fn json(params: &[Value], _: &mut StdRng) -> Result<String, String> {
+    match params.first() {
+        Some(value) => Ok(value.to_string()),
+        None => Err("expected a value as argument 1".to_string()),
+    }
+}
+
+fn first_name(_: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    Ok(FIRST_NAMES.choose(rng).unwrap().to_string())
+}
+
+fn last_name(_: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    Ok(LAST_NAMES.choose(rng).unwrap().to_string())
+}
+
+fn name(params: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    Ok(format!(
+        "{} {}",
+        first_name(params, rng)?,
+        last_name(params, rng)?
+    ))
+}
+
+fn email(params: &[Value], rng: &mut StdRng) -> Result<String, String> {
+    Ok(format!(
+        "{}.{}{}@example.com",
+        first_name(params, rng)?,
+        last_name(params, rng)?,
+        rng.gen_range(1..1000)
+    )
+    .to_lowercase())
+}
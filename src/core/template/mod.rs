@@ -0,0 +1,255 @@
+mod helpers;
+
+use crate::utils::error::CliError;
+use handlebars::template::{HelperTemplate, Parameter, Template, TemplateElement};
+use handlebars::{no_escape, Handlebars, Path};
+use helpers::{Function, HELPERS};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+pub const SEED_ENV_VAR: &str = "HIT_SEED";
+
+/// Renders the `{{VARIABLE}}` templates in the url, headers and body of a
+/// command. In strict mode, rendering an undefined variable fails.
+///
+/// The random values generated by the helpers are seeded from $HIT_SEED when
+/// it is set, so that they can be reproduced.
+pub struct Renderer {
+    handlebars: Handlebars<'static>,
+    data: HashMap<String, String>,
+    env_values: Arc<Mutex<Vec<String>>>,
+}
+
+impl Renderer {
+    pub fn new(data: HashMap<String, String>, strict: bool) -> Result<Renderer, CliError> {
+        let rng = match std::env::var(SEED_ENV_VAR) {
+            Ok(seed) => StdRng::seed_from_u64(seed.parse().map_err(|_| CliError {
+                message: format!("${} must be a number, got `{}`", SEED_ENV_VAR, seed),
+                help: None,
+            })?),
+            Err(_) => StdRng::from_entropy(),
+        };
+        Ok(Renderer::with_rng(data, strict, rng))
+    }
+
+    fn with_rng(data: HashMap<String, String>, strict: bool, rng: StdRng) -> Renderer {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(strict);
+        // NOTE: values are not HTML, so they are rendered as is
+        handlebars.register_escape_fn(no_escape);
+
+        let rng = Arc::new(Mutex::new(rng));
+        let env_values = Arc::new(Mutex::new(Vec::new()));
+        for (name, function) in HELPERS {
+            handlebars.register_helper(
+                name,
+                Box::new(Function {
+                    function: *function,
+                    rng: rng.clone(),
+                    env_values: env_values.clone(),
+                }),
+            );
+        }
+        Renderer {
+            handlebars,
+            data,
+            env_values,
+        }
+    }
+
+    pub fn render(&self, template: &str) -> Result<String, CliError> {
+        self.handlebars
+            .render_template(template, &self.data)
+            .map_err(|e| CliError {
+                message: format!("could not render template: {}", e),
+                help: None,
+            })
+    }
+
+    pub fn data(&self) -> &HashMap<String, String> {
+        &self.data
+    }
+
+    /// Returns the values read from the environment by the `env` helper so
+    /// far, which are masked like secrets.
+    pub fn env_values(&self) -> Vec<String> {
+        self.env_values.lock().unwrap().clone()
+    }
+}
+
+/// Returns the names of the variables used in `template`, sorted. Helpers
+/// called with arguments are not variables, but the variables passed to them
+/// are, and so are the ones used in blocks, e.g. `{{#if API}}`. A name like
+/// `{{email}}` is returned even though it is a helper, as a variable of that
+/// name takes precedence; see `is_helper`.
+pub fn get_variables(template: &str) -> BTreeSet<String> {
+    let mut variables = BTreeSet::new();
+    // NOTE: a template that does not compile fails when it is rendered
+    if let Ok(template) = Template::compile(template) {
+        add_template_variables(&template, &mut variables);
+    }
+    variables
+}
+
+fn add_template_variables(template: &Template, variables: &mut BTreeSet<String>) {
+    for element in &template.elements {
+        match element {
+            TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper)
+            | TemplateElement::HelperBlock(helper) => add_helper_variables(helper, variables),
+            _ => {}
+        }
+    }
+}
+
+fn add_helper_variables(helper: &HelperTemplate, variables: &mut BTreeSet<String>) {
+    if !helper.block && helper.params.is_empty() && helper.hash.is_empty() {
+        add_parameter_variables(&helper.name, variables);
+    }
+    for parameter in helper.params.iter().chain(helper.hash.values()) {
+        add_parameter_variables(parameter, variables);
+    }
+    for template in helper.template.iter().chain(&helper.inverse) {
+        add_template_variables(template, variables);
+    }
+}
+
+fn add_parameter_variables(parameter: &Parameter, variables: &mut BTreeSet<String>) {
+    let name = match parameter {
+        Parameter::Name(name) | Parameter::Path(Path::Relative((_, name))) => name,
+        Parameter::Subexpression(subexpression) => {
+            if let TemplateElement::Expression(helper) = subexpression.as_element() {
+                add_helper_variables(helper, variables);
+            }
+            return;
+        }
+        // NOTE: local paths, like `@index`, and literals are not variables
+        Parameter::Path(Path::Local(_)) | Parameter::Literal(_) => return,
+    };
+    let name_regex = Regex::new(r"^[A-Za-z_]\w*$").unwrap();
+    if name_regex.is_match(name) && name != "this" {
+        variables.insert(name.to_string());
+    }
+}
+
+/// Whether `name` is a helper, which is used when no variable of that name is
+/// defined.
+pub fn is_helper(name: &str) -> bool {
+    HELPERS.iter().any(|(helper, _)| *helper == name)
+}
+
+pub fn undefined_variables(names: &[&String], env: &str) -> CliError {
+    let names: Vec<String> = names.iter().map(|name| format!("`{}`", name)).collect();
+    CliError {
+        message: format!(
+            "undefined variable{} {}",
+            if names.len() == 1 { "" } else { "s" },
+            names.join(", ")
+        ),
+        help: Some(format!(
+            "looked up in env `{}` and the ephenvs for it; define them with `hit env set` or `hit ephenv set`, or pass --lenient to render them as empty",
+            env
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn data() -> HashMap<String, String> {
+        HashMap::from([("NAME".to_string(), "<a & b>".to_string())])
+    }
+
+    fn renderer(strict: bool) -> Renderer {
+        Renderer::with_rng(data(), strict, StdRng::seed_from_u64(7))
+    }
+
+    #[rstest]
+    fn test_render_does_not_escape() {
+        let renderer = renderer(true);
+        assert_eq!(renderer.render("{{NAME}}").unwrap(), "<a & b>");
+    }
+
+    #[rstest]
+    #[case(true, None)]
+    #[case(false, Some("/"))]
+    fn test_render_undefined_variable(#[case] strict: bool, #[case] expected: Option<&str>) {
+        let renderer = renderer(strict);
+        assert_eq!(renderer.render("/{{MISSING}}").ok().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("{{ B }}/{{A}}/{{B}}", &["A", "B"])]
+    #[case("{{API}}/c/{{#if API}}x{{else}}y{{/if}}", &["API"])]
+    #[case("{{#each ITEMS}}{{this}}{{@index}}{{/each}}", &["ITEMS"])]
+    #[case("{{!-- note --}}{{! other note }}{{A}}", &["A"])]
+    #[case("{{#unless A}}{{B}}{{else}}{{C}}{{/unless}}", &["A", "B", "C"])]
+    fn test_get_variables(#[case] template: &str, #[case] expected: &[&str]) {
+        assert_eq!(
+            get_variables(template).into_iter().collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("{{uuid}} {{now \"%Y\"}} {{random_int 1 9}}", &["uuid"])]
+    #[case("{{base64 (env \"HOME\")}} {{hmac_sha256 SECRET BODY}}", &["BODY", "SECRET"])]
+    #[case("{{{json NAME}}} {{sha256 'a b'}}", &["NAME"])]
+    #[case("{{name}} {{base64 env}}", &["env", "name"])]
+    fn test_get_variables_of_helpers(#[case] template: &str, #[case] expected: &[&str]) {
+        assert_eq!(
+            get_variables(template).into_iter().collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("{{base64 \"user:pass\"}}", "dXNlcjpwYXNz")]
+    #[case("{{urlencode \"a b&c\"}}", "a%20b%26c")]
+    #[case(
+        "{{sha256 \"abc\"}}",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    )]
+    #[case(
+        "{{hmac_sha256 \"key\" \"The quick brown fox jumps over the lazy dog\"}}",
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+    )]
+    #[case("{{json NAME}}", "\"<a & b>\"")]
+    #[case("{{random_int 3 3}}", "3")]
+    fn test_helpers(#[case] template: &str, #[case] expected: &str) {
+        assert_eq!(renderer(true).render(template).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn test_helpers_are_seeded() {
+        let template = "{{uuid}} {{random_string 8}} {{email}}";
+        let rendered = renderer(true).render(template).unwrap();
+        assert_eq!(rendered, renderer(true).render(template).unwrap());
+        assert_eq!(rendered.split(' ').nth(1).unwrap().len(), 8);
+        assert!(rendered.ends_with("@example.com"));
+    }
+
+    #[rstest]
+    #[case("{{email}}", "ada@example.org")]
+    #[case("{{env}}", "staging")]
+    #[case("{{base64 env}}", "c3RhZ2luZw==")]
+    fn test_variables_take_precedence_over_helpers(#[case] template: &str, #[case] expected: &str) {
+        let data = HashMap::from([
+            ("email".to_string(), "ada@example.org".to_string()),
+            ("env".to_string(), "staging".to_string()),
+        ]);
+        let renderer = Renderer::with_rng(data, true, StdRng::seed_from_u64(7));
+        assert_eq!(renderer.render(template).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn test_helper_errors() {
+        let error = renderer(true).render("{{timestamp \"days\"}}").unwrap_err();
+        assert!(error.message.contains("unknown unit `days`"));
+    }
+}
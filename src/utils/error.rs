@@ -13,3 +13,15 @@ impl fmt::Display for CliError {
     }
 }
 impl Error for CliError {}
+
+/// Describes an error along with its sources, e.g. `error sending request:
+/// connection refused`.
+pub fn describe_error(error: &dyn Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        description.push_str(&format!(": {}", error));
+        source = error.source();
+    }
+    description
+}
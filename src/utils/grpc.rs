@@ -0,0 +1,370 @@
+use crate::utils::error::{describe_error, CliError};
+use crate::utils::headers::Headers;
+use crate::utils::http::Response;
+use crate::utils::tls::Tls;
+use prost::Message as _;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use tempfile::NamedTempFile;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+use tonic_reflection::pb::v1::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::v1::ServerReflectionRequest;
+
+pub type ReplyHandler<'a> = Box<dyn FnMut(&Value) -> bool + 'a>;
+
+/// A call to a gRPC method, whose request and response types are resolved from
+/// `protos`, or from server reflection when there are none.
+pub struct Call<'a> {
+    pub url: &'a str,
+    pub service: &'a str,
+    pub method: &'a str,
+    /// `.proto` files, compiled with `protoc`, or descriptor sets
+    pub protos: &'a [PathBuf],
+    pub headers: &'a Headers,
+    /// Used when the url is https://
+    pub tls: &'a Tls,
+    /// The request message as JSON, or an array of messages for client
+    /// streaming methods
+    pub body: Option<&'a str>,
+    /// Receives every message of a server streaming response, until it
+    /// returns false
+    pub on_message: ReplyHandler<'a>,
+}
+
+pub struct Reply {
+    /// The response, with the message as a JSON body unless it was streamed
+    pub response: Response,
+    pub streamed: bool,
+}
+
+/// Encodes and decodes messages of the types resolved at runtime.
+#[derive(Clone)]
+struct DynamicCodec(MessageDescriptor);
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        item.encode(dst)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<DynamicMessage>, Status> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+fn status_error(status: Status) -> CliError {
+    CliError {
+        message: match status.message() {
+            "" => format!("gRPC call failed with {:?}", status.code()),
+            message => format!("gRPC call failed with {:?}: {}", status.code(), message),
+        },
+        help: None,
+    }
+}
+
+fn metadata_headers(metadata: &MetadataMap) -> Headers {
+    Headers::from_header_map(&metadata.clone().into_headers())
+}
+
+async fn connect(url: &str, tls: &Tls) -> Result<Channel, CliError> {
+    let connect_error = |e: &dyn Error| CliError {
+        message: format!("could not connect to {}: {}", url, describe_error(e)),
+        help: None,
+    };
+    let mut endpoint = Endpoint::from_shared(url.to_string()).map_err(|e| connect_error(&e))?;
+    if url.starts_with("https://") {
+        endpoint = endpoint
+            .tls_config(tls.grpc_config()?)
+            .map_err(|e| connect_error(&e))?;
+    }
+    endpoint.connect().await.map_err(|e| connect_error(&e))
+}
+
+fn compile_proto(path: &Path) -> Result<Vec<u8>, CliError> {
+    let output = NamedTempFile::new().map_err(|e| CliError {
+        message: e.to_string(),
+        help: None,
+    })?;
+    let result = StdCommand::new("protoc")
+        .arg("--include_imports")
+        .arg(format!("--descriptor_set_out={}", output.path().display()))
+        .arg("-I")
+        .arg(path.parent().unwrap_or(Path::new(".")))
+        .arg(path)
+        .output()
+        .map_err(|e| CliError {
+            message: format!("could not run protoc to compile {}: {}", path.display(), e),
+            help: Some(
+                "install protoc, or use a descriptor set generated with `protoc --include_imports --descriptor_set_out=<file>`"
+                    .to_string(),
+            ),
+        })?;
+    if !result.status.success() {
+        return Err(CliError {
+            message: format!(
+                "could not compile {}:\n{}",
+                path.display(),
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            ),
+            help: None,
+        });
+    }
+    fs::read(output.path()).map_err(|e| CliError {
+        message: e.to_string(),
+        help: None,
+    })
+}
+
+fn load_protos(paths: &[PathBuf]) -> Result<DescriptorPool, CliError> {
+    let mut pool = DescriptorPool::new();
+    for path in paths {
+        let bytes = match path
+            .extension()
+            .is_some_and(|extension| extension == "proto")
+        {
+            true => compile_proto(path)?,
+            false => fs::read(path).map_err(|e| CliError {
+                message: format!("could not read {}: {}", path.display(), e),
+                help: None,
+            })?,
+        };
+        FileDescriptorSet::decode(bytes.as_slice())
+            .map_err(|e| e.to_string())
+            .and_then(|set| pool.add_file_descriptor_set(set).map_err(|e| e.to_string()))
+            .map_err(|e| CliError {
+                message: format!("invalid descriptor set {}: {}", path.display(), e),
+                help: None,
+            })?;
+    }
+    Ok(pool)
+}
+
+/// Fetches the file defining `symbol` from the server, along with the files it
+/// depends on.
+async fn reflect(channel: Channel, symbol: &str) -> Result<DescriptorPool, CliError> {
+    let reflection_error = |message: String| CliError {
+        message: format!(
+            "could not resolve {} with server reflection: {}",
+            symbol, message
+        ),
+        help: Some(
+            "set `protos` on the command to the .proto files or descriptor sets of the service"
+                .to_string(),
+        ),
+    };
+
+    let mut client = ServerReflectionClient::new(channel);
+    let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+    let mut requested = HashSet::new();
+    let mut requests = vec![MessageRequest::FileContainingSymbol(symbol.to_string())];
+    while let Some(request) = requests.pop() {
+        let request = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(request),
+        };
+        let mut responses = client
+            .server_reflection_info(futures_util::stream::iter([request]))
+            .await
+            .map_err(|status| match status.code() {
+                Code::Unimplemented => {
+                    reflection_error("the server does not support reflection".to_string())
+                }
+                _ => reflection_error(status_error(status).message),
+            })?
+            .into_inner();
+        while let Some(response) = responses
+            .message()
+            .await
+            .map_err(|status| reflection_error(status_error(status).message))?
+        {
+            match response.message_response {
+                Some(MessageResponse::FileDescriptorResponse(response)) => {
+                    for bytes in response.file_descriptor_proto {
+                        let file = FileDescriptorProto::decode(bytes.as_slice())
+                            .map_err(|e| reflection_error(e.to_string()))?;
+                        files.insert(file.name().to_string(), file);
+                    }
+                }
+                Some(MessageResponse::ErrorResponse(error)) => {
+                    return Err(reflection_error(error.error_message))
+                }
+                _ => {}
+            }
+        }
+
+        for dependency in files.values().flat_map(|file| &file.dependency) {
+            if !files.contains_key(dependency) && requested.insert(dependency.clone()) {
+                requests.push(MessageRequest::FileByFilename(dependency.clone()));
+            }
+        }
+    }
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_protos(files.into_values())
+        .map_err(|e| reflection_error(e.to_string()))?;
+    Ok(pool)
+}
+
+fn find_method(
+    pool: &DescriptorPool,
+    service: &str,
+    method: &str,
+) -> Result<MethodDescriptor, CliError> {
+    let Some(service_descriptor) = pool.get_service_by_name(service) else {
+        let services: Vec<String> = pool.services().map(|s| s.full_name().to_string()).collect();
+        return Err(CliError {
+            message: format!("service `{}` not found", service),
+            help: Some(format!("the known services are {}", services.join(", "))),
+        });
+    };
+    let method_descriptor = service_descriptor.methods().find(|m| m.name() == method);
+    method_descriptor.ok_or_else(|| CliError {
+        message: format!("method `{}` not found in service `{}`", method, service),
+        help: Some(format!(
+            "the methods of the service are {}",
+            service_descriptor
+                .methods()
+                .map(|m| m.name().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    })
+}
+
+fn parse_messages(
+    method: &MethodDescriptor,
+    body: Option<&str>,
+) -> Result<Vec<DynamicMessage>, CliError> {
+    let input = method.input();
+    let invalid = |e: &dyn std::fmt::Display| CliError {
+        message: format!(
+            "the request body is not a valid {}: {}",
+            input.full_name(),
+            e
+        ),
+        help: None,
+    };
+    let values = match body.map(serde_json::from_str::<Value>).transpose() {
+        Ok(None) => return Ok(vec![DynamicMessage::new(input.clone())]),
+        Ok(Some(Value::Array(values))) if method.is_client_streaming() => values,
+        Ok(Some(value)) => vec![value],
+        Err(e) => return Err(invalid(&e)),
+    };
+    values
+        .into_iter()
+        .map(|value| DynamicMessage::deserialize(input.clone(), value).map_err(|e| invalid(&e)))
+        .collect()
+}
+
+pub async fn call(mut call: Call<'_>) -> Result<Reply, Box<dyn Error>> {
+    let channel = connect(call.url, call.tls).await?;
+    let pool = match call.protos.is_empty() {
+        true => reflect(channel.clone(), call.service).await?,
+        false => load_protos(call.protos)?,
+    };
+    let method = find_method(&pool, call.service, call.method)?;
+    let messages = parse_messages(&method, call.body)?;
+
+    let mut request = tonic::Request::new(futures_util::stream::iter(messages));
+    for (name, value) in call.headers.iter() {
+        let key = MetadataKey::from_bytes(name.to_lowercase().as_bytes()).ok();
+        let metadata_value = MetadataValue::try_from(value).ok();
+        let (Some(key), Some(metadata_value)) = (key, metadata_value) else {
+            return Err(Box::new(CliError {
+                message: format!("invalid metadata `{}: {}`", name, value),
+                help: None,
+            }));
+        };
+        request.metadata_mut().append(key, metadata_value);
+    }
+
+    let path = PathAndQuery::try_from(format!("/{}/{}", call.service, call.method))?;
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await?;
+    let response = grpc
+        .streaming(request, path, DynamicCodec(method.output()))
+        .await
+        .map_err(status_error)?;
+    let mut headers = metadata_headers(response.metadata());
+    let mut messages = response.into_inner();
+
+    let streamed = method.is_server_streaming();
+    let mut body = Vec::new();
+    let mut finished = false;
+    let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
+    loop {
+        let message = tokio::select! {
+            message = messages.message() => message.map_err(status_error)?,
+            _ = &mut ctrl_c => break,
+        };
+        let Some(message) = message else {
+            finished = true;
+            break;
+        };
+        let message = serde_json::to_value(&message)?;
+        if !streamed {
+            body = serde_json::to_vec(&message)?;
+        } else if !(call.on_message)(&message) {
+            break;
+        }
+    }
+    if finished {
+        if let Ok(Some(trailers)) = messages.trailers().await {
+            headers.extend(metadata_headers(&trailers));
+        }
+    }
+    // NOTE: the body is the JSON form of the response message
+    headers.set("content-type", "application/json");
+
+    Ok(Reply {
+        response: Response {
+            url: format!(
+                "{}/{}/{}",
+                call.url.trim_end_matches('/'),
+                call.service,
+                call.method
+            ),
+            status: 200,
+            headers,
+            body,
+        },
+        streamed,
+    })
+}
@@ -0,0 +1,241 @@
+use crate::utils::error::CliError;
+use indexmap::IndexMap;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Headers in the order they were set, where a name can have several values.
+/// Names are compared case-insensitively.
+///
+/// In JSON, a header is a string, or an array of strings when it has several
+/// values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Headers(Vec<(String, String)>);
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum HeaderValues {
+    One(String),
+    /// The values of a header sent more than once
+    Many(Vec<String>),
+}
+
+impl Headers {
+    pub fn new() -> Headers {
+        Headers(Vec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the first value of `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.push((name.into(), value.into()));
+    }
+
+    /// Replaces the values of `name` with `value`.
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.remove(name);
+        self.append(name, value);
+    }
+
+    /// Removes every value of `name`, returning whether there was any.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.0.len();
+        self.0
+            .retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+        self.0.len() != len
+    }
+
+    /// Replaces the values of the headers set in `headers`, and adds the others.
+    pub fn merge(&mut self, headers: Headers) {
+        for (name, _) in &headers.0 {
+            self.remove(name);
+        }
+        self.0.extend(headers.0);
+    }
+
+    /// Reads the headers of a request or response, keeping repeated ones.
+    pub fn from_header_map(headers: &HeaderMap) -> Headers {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), header_text(value.as_bytes())))
+            .collect()
+    }
+
+    /// Builds the headers to send, failing on names or values that are not
+    /// valid in HTTP.
+    pub fn to_header_map(&self) -> Result<HeaderMap, CliError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.0 {
+            let header_name = HeaderName::from_bytes(name.as_bytes());
+            let header_value = HeaderValue::from_str(value);
+            let (Ok(header_name), Ok(header_value)) = (header_name, header_value) else {
+                return Err(CliError {
+                    message: format!("invalid header `{}: {}`", name, value),
+                    help: None,
+                });
+            };
+            headers.append(header_name, header_value);
+        }
+        Ok(headers)
+    }
+}
+
+/// Decodes a header value, escaping the bytes that are not UTF-8 as `\xNN`
+/// so that none are lost, and backslashes as `\\` so that the escapes are
+/// unambiguous.
+pub fn header_text(value: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in value.utf8_chunks() {
+        text.push_str(&chunk.valid().replace('\\', "\\\\"));
+        for byte in chunk.invalid() {
+            text.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    text
+}
+
+impl FromIterator<(String, String)> for Headers {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Headers {
+        Headers(iter.into_iter().collect())
+    }
+}
+
+impl Extend<(String, String)> for Headers {
+    fn extend<T: IntoIterator<Item = (String, String)>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Headers {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Serialize for Headers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut grouped: IndexMap<String, (&str, Vec<&str>)> = IndexMap::new();
+        for (name, value) in &self.0 {
+            grouped
+                .entry(name.to_lowercase())
+                .or_insert((name, Vec::new()))
+                .1
+                .push(value);
+        }
+        let mut map = serializer.serialize_map(Some(grouped.len()))?;
+        for (name, values) in grouped.values() {
+            match values.as_slice() {
+                [value] => map.serialize_entry(name, value)?,
+                values => map.serialize_entry(name, values)?,
+            }
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Headers, D::Error> {
+        let headers = IndexMap::<String, HeaderValues>::deserialize(deserializer)?;
+        Ok(headers
+            .into_iter()
+            .flat_map(|(name, values)| {
+                let values = match values {
+                    HeaderValues::One(value) => vec![value],
+                    HeaderValues::Many(values) => values,
+                };
+                values.into_iter().map(move |value| (name.clone(), value))
+            })
+            .collect())
+    }
+}
+
+impl JsonSchema for Headers {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "Headers".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        IndexMap::<String, HeaderValues>::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_repeated_headers_round_trip() {
+        let value = json!({
+            "content-type": "text/html",
+            "set-cookie": ["a=1", "b=2"]
+        });
+        let headers: Headers = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(headers.get("Content-Type"), Some("text/html"));
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec![
+                ("content-type", "text/html"),
+                ("set-cookie", "a=1"),
+                ("set-cookie", "b=2")
+            ]
+        );
+        assert_eq!(serde_json::to_value(&headers).unwrap(), value);
+    }
+
+    #[rstest]
+    fn test_merge_replaces_values() {
+        let mut headers: Headers = serde_json::from_value(json!({
+            "Accept": ["text/html", "application/xml"],
+            "X-Team": "api"
+        }))
+        .unwrap();
+        headers.merge(Headers::from_iter([(
+            "accept".to_string(),
+            "application/json".to_string(),
+        )]));
+        assert_eq!(
+            serde_json::to_value(&headers).unwrap(),
+            json!({ "X-Team": "api", "accept": "application/json" })
+        );
+    }
+
+    #[rstest]
+    #[case("café".as_bytes(), "café")]
+    #[case(b"caf\xe9", "caf\\xe9")]
+    #[case(b"C:\\xe9", "C:\\\\xe9")]
+    fn test_header_text(#[case] value: &[u8], #[case] expected: &str) {
+        assert_eq!(header_text(value), expected);
+    }
+}
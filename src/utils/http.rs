@@ -1,9 +1,24 @@
+use crate::utils::error::{describe_error, CliError};
+use crate::utils::headers::Headers;
+use crate::utils::tls::Tls;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest;
+use reqwest::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, USER_AGENT};
+use reqwest::multipart;
+use reqwest_cookie_store::CookieStoreMutex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use strum::Display;
+use std::error::Error;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use strum::{Display, EnumIter, EnumString};
+use tokio::io::AsyncWriteExt;
 
-#[derive(Display, Deserialize, Serialize, Clone, Debug)]
+#[derive(
+    Display, EnumIter, EnumString, Deserialize, Serialize, JsonSchema, Clone, Debug, PartialEq,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum HttpMethod {
     GET,
     POST,
@@ -12,21 +27,306 @@ pub enum HttpMethod {
     PATCH,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Serialize, Clone)]
 pub struct Response {
     pub url: String,
     pub status: u16,
-    pub headers: HashMap<String, String>,
-    pub body: String,
+    pub headers: Headers,
+    #[serde(serialize_with = "serialize_body")]
+    pub body: Vec<u8>,
+}
+
+fn serialize_body<S: serde::Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&String::from_utf8_lossy(body))
+}
+
+/// Content types that are text even though they are not `text/*`.
+const TEXT_CONTENT_TYPES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/javascript",
+    "application/x-www-form-urlencoded",
+    "application/yaml",
+    "application/x-yaml",
+    "application/graphql",
+    "application/x-ndjson",
+];
+
+/// Content types of bodies that are sent bit by bit over a long-lived
+/// response, and are read as they arrive.
+const STREAMING_CONTENT_TYPES: &[&str] = &[
+    "text/event-stream",
+    "application/x-ndjson",
+    "application/ndjson",
+    "application/jsonl",
+    "application/stream+json",
+];
+
+impl Response {
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get(CONTENT_TYPE.as_str())
+            .map(|content_type| content_type.split(';').next().unwrap().trim())
+    }
+
+    /// Whether the body should not be printed as text. Without a content type,
+    /// bodies that are not valid UTF-8 are binary.
+    pub fn is_binary(&self) -> bool {
+        match self.content_type().map(str::to_lowercase) {
+            Some(content_type) => {
+                !(content_type.starts_with("text/")
+                    || content_type.ends_with("+json")
+                    || content_type.ends_with("+xml")
+                    || TEXT_CONTENT_TYPES.contains(&content_type.as_str()))
+            }
+            None => std::str::from_utf8(&self.body).is_err() || self.body.contains(&0),
+        }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.content_type().is_some_and(|content_type| {
+            STREAMING_CONTENT_TYPES.contains(&content_type.to_lowercase().as_str())
+        })
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Where the body of a response goes. Unless it is kept in memory, the body
+/// of the returned response is empty.
+pub enum ResponseSink<'a> {
+    Download(Download<'a>),
+    Stream(Stream<'a>),
+}
+
+/// Streams the response body to a file. `on_progress` is called with the
+/// number of bytes written so far and the expected total, when known.
+pub struct Download<'a> {
+    pub path: &'a Path,
+    pub on_progress: Box<dyn FnMut(u64, Option<u64>) + 'a>,
+}
+
+pub type ChunkHandler<'a> = Box<dyn FnMut(&Response, &[u8]) -> bool + 'a>;
+
+/// Passes the chunks of a streaming response to `on_chunk` as they arrive,
+/// until it returns false, the response ends or Ctrl-C is pressed. Other
+/// responses are kept in memory, unless `force` is set.
+pub struct Stream<'a> {
+    pub force: bool,
+    pub on_chunk: ChunkHandler<'a>,
+}
+
+pub enum RequestBody {
+    /// Sent as JSON when it parses as JSON, and as is otherwise
+    Json(String),
+    Text(String),
+    Form(Vec<(String, String)>),
+    Multipart(Vec<(String, Part)>),
+    File(PathBuf),
+}
+
+pub enum Part {
+    Text(String),
+    File(PathBuf),
+}
+
+impl RequestBody {
+    /// Describes the body the way it will be sent, without reading any file.
+    pub fn describe(&self) -> String {
+        match self {
+            RequestBody::Json(text) | RequestBody::Text(text) => text.clone(),
+            RequestBody::Form(fields) => fields
+                .iter()
+                .map(|(name, value)| {
+                    format!(
+                        "{}={}",
+                        utf8_percent_encode(name, NON_ALPHANUMERIC),
+                        utf8_percent_encode(value, NON_ALPHANUMERIC)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("&"),
+            RequestBody::Multipart(parts) => parts
+                .iter()
+                .map(|(name, part)| match part {
+                    Part::Text(value) => format!("{}: {}", name, value),
+                    Part::File(path) => format!("{}: @{}", name, path.display()),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            RequestBody::File(path) => format!("@{}", path.display()),
+        }
+    }
+}
+
+fn file_error(path: &Path, error: std::io::Error) -> CliError {
+    CliError {
+        message: format!("could not read {}: {}", path.display(), error),
+        help: None,
+    }
+}
+
+fn write_error(path: &Path, error: std::io::Error) -> CliError {
+    CliError {
+        message: format!("could not write {}: {}", path.display(), error),
+        help: None,
+    }
+}
+
+/// How the requests of an env reach the server.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct NetworkConfig {
+    /// URL of an HTTP, HTTPS or SOCKS5 proxy, e.g. `socks5h://localhost:1080`,
+    /// which can use variables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Hosts reached without the proxy, e.g. `localhost,.internal`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    /// Addresses to connect to instead of resolving hosts, as
+    /// `HOST:PORT:ADDRESS`, e.g. `api.example.com:443:10.0.0.5`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolve: Vec<String>,
+    /// Unix socket to send requests over, e.g. `/var/run/docker.sock`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<PathBuf>,
+}
+
+impl NetworkConfig {
+    /// Fails on the settings that only HTTP requests support, for the
+    /// `commands` of another kind, e.g. websocket.
+    pub fn check_supported(&self, commands: &str) -> Result<(), CliError> {
+        let unsupported = |setting: &str| CliError {
+            message: format!("{} is not supported for {} commands", setting, commands),
+            help: None,
+        };
+        if self.proxy.is_some() {
+            return Err(unsupported("proxy"));
+        }
+        if !self.resolve.is_empty() {
+            return Err(unsupported("resolve"));
+        }
+        if self.unix_socket.is_some() {
+            return Err(unsupported("unix_socket"));
+        }
+        Ok(())
+    }
+}
+
+fn resolve_override(entry: &str) -> Result<(&str, u16, IpAddr), CliError> {
+    let invalid = || CliError {
+        message: format!("invalid resolve entry `{}`", entry),
+        help: Some("use HOST:PORT:ADDRESS, e.g. example.com:443:127.0.0.1".to_string()),
+    };
+    let mut parts = entry.splitn(3, ':');
+    let (Some(host), Some(port), Some(address)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let port = port.parse().map_err(|_| invalid())?;
+    let address = address
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .map_err(|_| invalid())?;
+    Ok((host, port, address))
+}
+
+/// Splits a `unix:<socket>:<path>` url into the socket and the url of the
+/// path, e.g. `unix:/var/run/docker.sock:/containers/json`.
+pub fn unix_socket_url(url: &str) -> Option<(PathBuf, String)> {
+    let (socket, path) = url.strip_prefix("unix:")?.split_once(':')?;
+    Some((PathBuf::from(socket), format!("http://localhost{}", path)))
+}
+
+/// A client for requests to `url`, which records the certificate of the
+/// server when `tls_info` is set.
+pub fn client(
+    url: &str,
+    tls: &Tls,
+    network: &NetworkConfig,
+    cookies: Option<Arc<CookieStoreMutex>>,
+    tls_info: bool,
+) -> Result<reqwest::Client, CliError> {
+    let mut builder = reqwest::Client::builder()
+        .use_preconfigured_tls(tls.connector()?)
+        .tls_info(tls_info);
+    if let Some(jar) = cookies {
+        builder = builder.cookie_provider(jar);
+    }
+    if let Some(proxy) = &network.proxy {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| CliError {
+            message: format!("invalid proxy `{}`: {}", proxy, describe_error(&e)),
+            help: None,
+        })?;
+        let no_proxy = network
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string);
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+    // NOTE: overrides apply to a host on any port, so only those for the
+    // port of the url are used
+    let url = reqwest::Url::parse(url).ok();
+    for entry in &network.resolve {
+        let (host, port, address) = resolve_override(entry)?;
+        if url.as_ref().is_some_and(|url| {
+            url.host_str()
+                .is_some_and(|url_host| url_host.eq_ignore_ascii_case(host))
+                && url.port_or_known_default() == Some(port)
+        }) {
+            builder = builder.resolve(host, SocketAddr::new(address, port));
+        }
+    }
+    if let Some(socket) = &network.unix_socket {
+        #[cfg(unix)]
+        {
+            builder = builder.unix_socket(socket.as_path());
+        }
+        #[cfg(not(unix))]
+        return Err(CliError {
+            message: format!(
+                "cannot send requests over the unix socket {} on this platform",
+                socket.display()
+            ),
+            help: None,
+        });
+    }
+    builder.build().map_err(|e| CliError {
+        message: format!("could not set up the HTTP client: {}", describe_error(&e)),
+        help: None,
+    })
+}
+
+/// How a response arrived: its HTTP version and, over TLS, the certificate
+/// of the server, when the client was built to record it.
+pub struct Connection<'a> {
+    pub version: reqwest::Version,
+    pub peer_certificate: Option<&'a [u8]>,
+}
+
+pub type ConnectionHandler<'a> = Box<dyn FnOnce(&Connection) + 'a>;
+
+fn request_error(error: reqwest::Error) -> CliError {
+    let description = describe_error(&error);
+    CliError {
+        help: description.contains("certificate").then(|| {
+            "trust its CA with `ca_certs` in the `tls` settings of the command or env, or pass --insecure to skip verification".to_string()
+        }),
+        message: description,
+    }
 }
 
 pub async fn handle_request(
+    client: &reqwest::Client,
     url: String,
     http_method: &HttpMethod,
-    headers: &HashMap<String, String>,
-    body: Option<String>,
-) -> Result<Response, reqwest::Error> {
-    let client = reqwest::Client::new();
+    headers: &Headers,
+    body: Option<RequestBody>,
+    sink: ResponseSink<'_>,
+    on_connected: ConnectionHandler<'_>,
+) -> Result<Response, Box<dyn Error>> {
     let method: reqwest::Method = match http_method {
         HttpMethod::GET => reqwest::Method::GET,
         HttpMethod::POST => reqwest::Method::POST,
@@ -34,44 +334,184 @@ pub async fn handle_request(
         HttpMethod::DELETE => reqwest::Method::DELETE,
         HttpMethod::PATCH => reqwest::Method::PATCH,
     };
-    let request = reqwest::Request::new(method, reqwest::Url::parse(&url).expect("Invalid url"));
-
-    let mut owned_headers = headers.clone();
-    owned_headers.insert("User-Agent".to_string(), "hit-cli".to_string());
+    let parsed_url = reqwest::Url::parse(&url).map_err(|e| CliError {
+        message: format!("invalid url {}: {}", url, e),
+        help: None,
+    })?;
+    let request = reqwest::Request::new(method, parsed_url);
 
-    let mut headers_map = reqwest::header::HeaderMap::new();
+    let mut headers_map = headers.to_header_map()?;
+    headers_map
+        .entry(USER_AGENT)
+        .or_insert(HeaderValue::from_static("hit-cli"));
 
-    headers_map.extend(owned_headers.into_iter().map(|(k, v)| {
-        (
-            reqwest::header::HeaderName::from_bytes(k.as_bytes()).unwrap(),
-            reqwest::header::HeaderValue::from_str(&v).unwrap(),
-        )
-    }));
+    match &body {
+        Some(RequestBody::Text(_)) => {
+            headers_map
+                .entry(CONTENT_TYPE)
+                .or_insert(HeaderValue::from_static("text/plain; charset=utf-8"));
+        }
+        Some(RequestBody::File(_)) => {
+            headers_map
+                .entry(CONTENT_TYPE)
+                .or_insert(HeaderValue::from_static("application/octet-stream"));
+        }
+        // NOTE: the content type carries the boundary, so it cannot be
+        // provided by the command
+        Some(RequestBody::Multipart(_)) => {
+            headers_map.remove(CONTENT_TYPE);
+        }
+        _ => {}
+    }
 
-    let request_builder = reqwest::RequestBuilder::from_parts(client, request).headers(headers_map);
+    let request_builder =
+        reqwest::RequestBuilder::from_parts(client.clone(), request).headers(headers_map);
 
     let request_builder = match body {
-        Some(body) => {
+        Some(RequestBody::Json(body)) => {
             if let Ok(json_body) = serde_json::from_str::<serde_json::Value>(&body) {
                 request_builder.json(&json_body)
             } else {
                 request_builder.body(body)
             }
         }
+        Some(RequestBody::Text(body)) => request_builder.body(body),
+        Some(RequestBody::Form(fields)) => request_builder.form(&fields),
+        Some(RequestBody::Multipart(parts)) => {
+            let mut form = multipart::Form::new();
+            for (name, part) in parts {
+                form = match part {
+                    Part::Text(value) => form.text(name, value),
+                    Part::File(path) => form.part(
+                        name,
+                        multipart::Part::file(&path)
+                            .await
+                            .map_err(|e| file_error(&path, e))?,
+                    ),
+                };
+            }
+            request_builder.multipart(form)
+        }
+        Some(RequestBody::File(path)) => {
+            let file = tokio::fs::File::open(&path)
+                .await
+                .map_err(|e| file_error(&path, e))?;
+            let length = file
+                .metadata()
+                .await
+                .map_err(|e| file_error(&path, e))?
+                .len();
+            request_builder.header(CONTENT_LENGTH, length).body(file)
+        }
         None => request_builder,
     };
 
-    let response = request_builder.send().await?;
-    let mut response_headers = HashMap::new();
+    let mut response = request_builder.send().await.map_err(request_error)?;
+    on_connected(&Connection {
+        version: response.version(),
+        peer_certificate: response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|info| info.peer_certificate()),
+    });
+    let mut head = Response {
+        url: response.url().clone().to_string(),
+        status: response.status().as_u16(),
+        headers: Headers::from_header_map(response.headers()),
+        body: Vec::new(),
+    };
+    head.body = match sink {
+        ResponseSink::Download(mut download) => {
+            let mut file = tokio::fs::File::create(download.path)
+                .await
+                .map_err(|e| write_error(download.path, e))?;
+            let total = response.content_length();
+            let mut written = 0;
+            (download.on_progress)(written, total);
+            while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+                file.write_all(&chunk)
+                    .await
+                    .map_err(|e| write_error(download.path, e))?;
+                written += chunk.len() as u64;
+                (download.on_progress)(written, total);
+            }
+            file.flush()
+                .await
+                .map_err(|e| write_error(download.path, e))?;
+            Vec::new()
+        }
+        ResponseSink::Stream(mut stream) if stream.force || head.is_streaming() => {
+            let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
+            loop {
+                let chunk = tokio::select! {
+                    chunk = response.chunk() => chunk.map_err(request_error)?,
+                    _ = &mut ctrl_c => None,
+                };
+                match chunk {
+                    Some(chunk) if (stream.on_chunk)(&head, &chunk) => {}
+                    _ => break,
+                }
+            }
+            Vec::new()
+        }
+        _ => response.bytes().await.map_err(request_error)?.to_vec(),
+    };
+
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
 
-    for (key, value) in response.headers().iter() {
-        response_headers.insert(key.to_string(), value.to_str().unwrap().to_string());
+    #[rstest]
+    #[case(Some("application/json; charset=utf-8"), b"{}", false)]
+    #[case(Some("application/problem+json"), b"{}", false)]
+    #[case(Some("text/csv"), b"a,b", false)]
+    #[case(Some("image/png"), b"png", true)]
+    #[case(Some("application/octet-stream"), b"abc", true)]
+    #[case(None, b"plain", false)]
+    #[case(None, b"\xff\xfe", true)]
+    fn test_is_binary(
+        #[case] content_type: Option<&str>,
+        #[case] body: &[u8],
+        #[case] expected: bool,
+    ) {
+        let response = Response {
+            url: String::new(),
+            status: 200,
+            headers: content_type
+                .map(|content_type| ("content-type".to_string(), content_type.to_string()))
+                .into_iter()
+                .collect(),
+            body: body.to_vec(),
+        };
+        assert_eq!(response.is_binary(), expected);
     }
 
-    Ok(Response {
-        url: response.url().clone().to_string(),
-        status: response.status().as_u16(),
-        headers: response_headers,
-        body: response.text().await?,
-    })
+    #[rstest]
+    #[case("unix:/var/run/docker.sock:/containers/json", Some(("/var/run/docker.sock", "http://localhost/containers/json")))]
+    #[case("unix:docker.sock:/", Some(("docker.sock", "http://localhost/")))]
+    #[case("unix:/var/run/docker.sock", None)]
+    #[case("http://localhost/containers/json", None)]
+    fn test_unix_socket_url(#[case] url: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(
+            unix_socket_url(url),
+            expected.map(|(socket, url)| (PathBuf::from(socket), url.to_string()))
+        );
+    }
+
+    #[rstest]
+    #[case("example.com:443:10.0.0.5", Some(("example.com", 443, "10.0.0.5")))]
+    #[case("example.com:80:[::1]", Some(("example.com", 80, "::1")))]
+    #[case("example.com:80:::1", Some(("example.com", 80, "::1")))]
+    #[case("example.com:10.0.0.5", None)]
+    #[case("example.com:443:localhost", None)]
+    fn test_resolve_override(#[case] entry: &str, #[case] expected: Option<(&str, u16, &str)>) {
+        assert_eq!(
+            resolve_override(entry).ok(),
+            expected.map(|(host, port, address)| (host, port, address.parse().unwrap()))
+        );
+    }
 }
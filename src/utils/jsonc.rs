@@ -0,0 +1,297 @@
+use jsonc_parser::cst::{
+    CstComment, CstInputValue, CstNode, CstObject, CstObjectProp, CstRootNode,
+};
+use jsonc_parser::errors::ParseError;
+use jsonc_parser::ParseOptions;
+use serde_json::{Map, Value};
+
+pub fn has_comments(text: &str) -> Result<bool, ParseError> {
+    let root = CstRootNode::parse(text, &ParseOptions::default())?;
+    Ok(root.children().iter().any(node_has_comments))
+}
+
+fn node_has_comments(node: &CstNode) -> bool {
+    node.is_comment() || node.children().iter().any(node_has_comments)
+}
+
+/// Rewrites `text`, which holds `old`, to hold `new` instead. Only the values
+/// that differ are rewritten, so comments and formatting elsewhere are kept.
+pub fn update(text: &str, old: &Value, new: &Value) -> Result<String, ParseError> {
+    let root = CstRootNode::parse(text, &ParseOptions::default())?;
+    match (root.object_value(), old, new) {
+        (Some(object), Value::Object(old), Value::Object(new)) => update_object(&object, old, new),
+        _ => root.set_value(to_input_value(new)),
+    }
+    Ok(root.to_string())
+}
+
+fn update_object(object: &CstObject, old: &Map<String, Value>, new: &Map<String, Value>) {
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        if let Some(prop) = object.get(key) {
+            prop.remove();
+        }
+    }
+
+    let mut previous_key: Option<&String> = None;
+    for (key, value) in new {
+        match (object.get(key), old.get(key)) {
+            (Some(_), Some(old_value)) if old_value == value => {}
+            (Some(prop), Some(Value::Object(old_map))) => match (prop.object_value(), value) {
+                (Some(child), Value::Object(new_map)) => update_object(&child, old_map, new_map),
+                _ => prop.set_value(to_input_value(value)),
+            },
+            (Some(prop), _) => prop.set_value(to_input_value(value)),
+            (None, _) => {
+                // NOTE: new properties go right after the one preceding them
+                let index = previous_key
+                    .and_then(|previous_key| object.get(previous_key))
+                    .map_or(0, |prop| prop.property_index() + 1);
+                object.insert(index, key, to_input_value(value));
+            }
+        }
+        previous_key = Some(key);
+    }
+}
+
+/// Lays `text` out like `serde_json::to_string_pretty`, keeping its comments:
+/// a comment stays on the line of the value it follows, or on a line of its
+/// own.
+pub fn format(text: &str) -> Result<String, ParseError> {
+    let root = CstRootNode::parse(text, &ParseOptions::default())?;
+    let mut out = String::new();
+    for entry in entries(&root.children(), false) {
+        match entry {
+            Entry::Value(node) => {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                write_value(&node, 0, &mut out);
+            }
+            Entry::Comment(comment, same_line) => {
+                if same_line {
+                    out.push(' ');
+                } else if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&comment.raw_value());
+            }
+        }
+    }
+    out.push('\n');
+    Ok(out)
+}
+
+enum Entry {
+    Value(CstNode),
+    /// A comment, and whether it is on the line of the value before it
+    Comment(CstComment, bool),
+}
+
+/// The values and comments among `nodes`, leaving out whitespace and tokens.
+fn entries(nodes: &[CstNode], mut same_line: bool) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for node in nodes {
+        if node.is_newline() {
+            same_line = false;
+        } else if let Some(comment) = node.as_comment() {
+            entries.push(Entry::Comment(comment, same_line));
+        } else if !node.is_token() && !node.is_whitespace() {
+            entries.push(Entry::Value(node.clone()));
+            same_line = true;
+        }
+    }
+    entries
+}
+
+fn write_indent(indent: usize, out: &mut String) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(indent));
+}
+
+fn write_value(node: &CstNode, indent: usize, out: &mut String) {
+    let (children, open, close) = match (node.as_object(), node.as_array()) {
+        (Some(object), _) => (object.children(), '{', '}'),
+        (_, Some(array)) => (array.children(), '[', ']'),
+        _ => {
+            out.push_str(&node.to_string());
+            return;
+        }
+    };
+    let entries = entries(&children, true);
+    let count = entries
+        .iter()
+        .filter(|entry| matches!(entry, Entry::Value(_)))
+        .count();
+    out.push(open);
+    if entries.is_empty() {
+        out.push(close);
+        return;
+    }
+    let mut written = 0;
+    for entry in entries {
+        let comments = match entry {
+            Entry::Value(node) => {
+                write_indent(indent + 1, out);
+                let comments = match node.as_object_prop() {
+                    Some(prop) => write_prop(&prop, indent + 1, out),
+                    None => {
+                        write_value(&node, indent + 1, out);
+                        Vec::new()
+                    }
+                };
+                written += 1;
+                if written < count {
+                    out.push(',');
+                }
+                comments
+            }
+            comment => vec![comment],
+        };
+        for comment in comments {
+            if let Entry::Comment(comment, same_line) = comment {
+                if same_line {
+                    out.push(' ');
+                } else {
+                    write_indent(indent + 1, out);
+                }
+                out.push_str(&comment.raw_value());
+            }
+        }
+    }
+    write_indent(indent, out);
+    out.push(close);
+}
+
+/// Writes a property, returning the comments that follow its value, which go
+/// after the comma.
+fn write_prop(prop: &CstObjectProp, indent: usize, out: &mut String) -> Vec<Entry> {
+    let name = prop.decoded_name().unwrap_or_default();
+    out.push_str(&serde_json::to_string(&name).unwrap());
+    out.push_str(": ");
+    let children = prop.children();
+    let Some(value) = prop.value() else {
+        return Vec::new();
+    };
+    let value_index = value.child_index();
+    for comment in children[..value_index]
+        .iter()
+        .filter_map(CstNode::as_comment)
+    {
+        out.push_str(&comment.raw_value());
+        if comment.is_line_comment() {
+            write_indent(indent, out);
+        } else {
+            out.push(' ');
+        }
+    }
+    write_value(&value, indent, out);
+    entries(&children[value_index + 1..], true)
+}
+
+fn to_input_value(value: &Value) -> CstInputValue {
+    match value {
+        Value::Null => CstInputValue::Null,
+        Value::Bool(value) => CstInputValue::Bool(*value),
+        Value::Number(value) => CstInputValue::Number(value.to_string()),
+        Value::String(value) => CstInputValue::String(value.clone()),
+        Value::Array(values) => CstInputValue::Array(values.iter().map(to_input_value).collect()),
+        Value::Object(map) => CstInputValue::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), to_input_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    fn test_update_keeps_comments() {
+        let text = r#"{
+  // the API to talk to
+  "url": "https://example.com", // prod
+  "headers": {
+    /* needed by the gateway */
+    "X-Team": "api",
+  },
+}"#;
+        let old = json!({
+            "url": "https://example.com",
+            "headers": { "X-Team": "api" }
+        });
+        let new = json!({
+            "method": "GET",
+            "url": "https://example.org",
+            "headers": { "X-Team": "api", "Accept": "application/json" }
+        });
+
+        assert_eq!(
+            update(text, &old, &new).unwrap(),
+            r#"{
+  "method": "GET",
+  // the API to talk to
+  "url": "https://example.org", // prod
+  "headers": {
+    /* needed by the gateway */
+    "X-Team": "api",
+    "Accept": "application/json",
+  },
+}"#
+        );
+    }
+
+    #[rstest]
+    fn test_format_keeps_comments() {
+        let text = r#"// the config
+{ "envs": {"prod": { // live
+  "API_URL": "https://example.com",   "empty": {},
+    /* unused */ "list": [1,
+  2, // two
+  ]},
+  },
+  unquoted: /* inline */ true
+}"#;
+
+        assert_eq!(
+            format(text).unwrap(),
+            r#"// the config
+{
+  "envs": {
+    "prod": { // live
+      "API_URL": "https://example.com",
+      "empty": {},
+      /* unused */
+      "list": [
+        1,
+        2 // two
+      ]
+    }
+  },
+  "unquoted": /* inline */ true
+}
+"#
+        );
+    }
+
+    #[rstest]
+    fn test_format_without_comments_matches_serde() {
+        let value = json!({ "a": [1, { "b": null }], "c": {}, "d": [] });
+        let text = serde_json::to_string(&value).unwrap();
+        assert_eq!(
+            format(&text).unwrap(),
+            serde_json::to_string_pretty(&value).unwrap() + "\n"
+        );
+    }
+
+    #[rstest]
+    #[case("{\"a\": 1}", false)]
+    #[case("{\"a\": 1 // one\n}", true)]
+    #[case("{\"a\": /* one */ 1}", true)]
+    fn test_has_comments(#[case] text: &str, #[case] expected: bool) {
+        assert_eq!(has_comments(text).unwrap(), expected);
+    }
+}
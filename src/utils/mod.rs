@@ -1,3 +1,10 @@
 pub mod error;
+pub mod grpc;
+pub mod headers;
 pub mod http;
 pub mod input;
+pub mod jsonc;
+pub mod secret;
+pub mod stream;
+pub mod tls;
+pub mod websocket;
@@ -0,0 +1,51 @@
+const SECRET_NAME_MARKERS: [&str; 6] = ["KEY", "TOKEN", "SECRET", "PASS", "AUTH", "CREDENTIAL"];
+pub const MASK: &str = "********";
+
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_NAME_MARKERS
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+/// Values that must not be shown in output, such as resolved secret references.
+#[derive(Default)]
+pub struct Secrets {
+    values: Vec<String>,
+}
+
+impl Secrets {
+    pub fn add(&mut self, value: &str) {
+        if !value.is_empty() && !self.values.iter().any(|v| v == value) {
+            self.values.push(value.to_string());
+            // replace longer values first so that a secret containing another
+            // one is masked as a whole
+            self.values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        }
+    }
+
+    pub fn mask(&self, input: &str) -> String {
+        self.values
+            .iter()
+            .fold(input.to_string(), |acc, value| acc.replace(value, MASK))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    fn test_mask_secrets() {
+        let mut secrets = Secrets::default();
+        secrets.add("abc");
+        secrets.add("abcdef");
+        secrets.add("");
+
+        assert_eq!(
+            secrets.mask("token=abcdef&key=abc"),
+            "token=********&key=********"
+        );
+    }
+}
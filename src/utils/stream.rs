@@ -0,0 +1,140 @@
+/// An event of a streaming response: a server-sent event, or a line of a
+/// line-delimited body such as NDJSON.
+#[derive(Debug, PartialEq)]
+pub struct Event {
+    pub name: Option<String>,
+    pub id: Option<String>,
+    pub data: String,
+}
+
+/// Splits the chunks of a streaming response into events, whatever the
+/// chunk boundaries are.
+pub struct EventParser {
+    server_sent: bool,
+    buffer: Vec<u8>,
+    name: Option<String>,
+    id: Option<String>,
+    data: Option<String>,
+}
+
+impl EventParser {
+    pub fn new(server_sent: bool) -> EventParser {
+        EventParser {
+            server_sent,
+            buffer: Vec::new(),
+            name: None,
+            id: None,
+            data: None,
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            events.extend(self.parse_line(line));
+        }
+        events
+    }
+
+    /// Returns the events left once the response has ended.
+    pub fn finish(&mut self) -> Vec<Event> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+        let mut events: Vec<Event> = self
+            .parse_line(line.trim_end_matches('\r'))
+            .into_iter()
+            .collect();
+        if self.server_sent {
+            events.extend(self.parse_line(""));
+        }
+        events
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<Event> {
+        if !self.server_sent {
+            return match line.trim() {
+                "" => None,
+                line => Some(Event {
+                    name: None,
+                    id: None,
+                    data: line.to_string(),
+                }),
+            };
+        }
+
+        if line.is_empty() {
+            let name = self.name.take();
+            return self.data.take().map(|data| Event {
+                name,
+                id: self.id.clone(),
+                data,
+            });
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.name = Some(value.to_string()),
+            "id" => self.id = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn event(name: Option<&str>, id: Option<&str>, data: &str) -> Event {
+        Event {
+            name: name.map(str::to_string),
+            id: id.map(str::to_string),
+            data: data.to_string(),
+        }
+    }
+
+    #[rstest]
+    fn test_server_sent_events() {
+        let mut parser = EventParser::new(true);
+        let mut events = parser.feed(b": keep-alive\r\n\r\nevent: update\r\nid: 1\r\nda");
+        events.extend(parser.feed(b"ta: {\"a\":\r\ndata:1}\r\n\r\ndata: last"));
+        events.extend(parser.finish());
+        assert_eq!(
+            events,
+            [
+                event(Some("update"), Some("1"), "{\"a\":\n1}"),
+                event(None, Some("1"), "last")
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_lines() {
+        let mut parser = EventParser::new(false);
+        let mut events = parser.feed(b"{\"a\":1}\n\n{\"b\"");
+        events.extend(parser.feed(b":2}\n{\"c\":3}"));
+        events.extend(parser.finish());
+        assert_eq!(
+            events,
+            [
+                event(None, None, "{\"a\":1}"),
+                event(None, None, "{\"b\":2}"),
+                event(None, None, "{\"c\":3}")
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::utils::error::{describe_error, CliError};
+use native_tls::{Certificate, Identity, Protocol, TlsConnector};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use x509_parser::pem::Pem;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// The minimum TLS version accepted from the server. TLS 1.3 cannot be
+/// required, since it is not supported by every system TLS library.
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Copy, Debug, PartialEq)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls10,
+    #[serde(rename = "1.1")]
+    Tls11,
+    #[serde(rename = "1.2")]
+    Tls12,
+}
+
+impl TlsVersion {
+    fn protocol(self) -> Protocol {
+        match self {
+            TlsVersion::Tls10 => Protocol::Tlsv10,
+            TlsVersion::Tls11 => Protocol::Tlsv11,
+            TlsVersion::Tls12 => Protocol::Tlsv12,
+        }
+    }
+}
+
+/// The TLS settings a request is sent with.
+#[derive(Default, Debug, PartialEq)]
+pub struct Tls {
+    /// PEM files of CA certificates trusted along with the system ones
+    pub ca_certs: Vec<PathBuf>,
+    /// A PEM certificate, or a PKCS#12 archive holding the certificate and
+    /// its key when it ends with `.p12` or `.pfx`
+    pub client_cert: Option<PathBuf>,
+    /// The PEM (PKCS#8) key of a PEM client certificate
+    pub client_key: Option<PathBuf>,
+    pub client_cert_password: Option<String>,
+    pub min_version: Option<TlsVersion>,
+    /// Accept any certificate the server presents
+    pub insecure: bool,
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|e| CliError {
+        message: format!("could not read {}: {}", path.display(), e),
+        help: None,
+    })
+}
+
+fn invalid(path: &Path, error: &dyn std::error::Error) -> CliError {
+    CliError {
+        message: format!("invalid certificate {}: {}", path.display(), error),
+        help: None,
+    }
+}
+
+fn is_pkcs12(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("p12") || extension.eq_ignore_ascii_case("pfx")
+        })
+}
+
+impl Tls {
+    fn client_key(&self, cert: &Path) -> Result<&Path, CliError> {
+        self.client_key.as_deref().ok_or_else(|| CliError {
+            message: format!(
+                "no client_key set for the client certificate {}",
+                cert.display()
+            ),
+            help: Some(
+                "set `client_key` to its PEM key, or use a PKCS#12 archive (.p12) holding both"
+                    .to_string(),
+            ),
+        })
+    }
+
+    /// A connector for HTTP and websocket requests.
+    pub fn connector(&self) -> Result<TlsConnector, CliError> {
+        let mut builder = TlsConnector::builder();
+        for path in &self.ca_certs {
+            let bundle = read(path)?;
+            for pem in Pem::iter_from_buffer(&bundle) {
+                let pem = pem.map_err(|e| invalid(path, &e))?;
+                builder.add_root_certificate(
+                    Certificate::from_der(&pem.contents).map_err(|e| invalid(path, &e))?,
+                );
+            }
+        }
+        if let Some(cert) = &self.client_cert {
+            let identity = if is_pkcs12(cert) {
+                Identity::from_pkcs12(
+                    &read(cert)?,
+                    self.client_cert_password.as_deref().unwrap_or_default(),
+                )
+            } else {
+                let key = read(self.client_key(cert)?)?;
+                Identity::from_pkcs8(&read(cert)?, &key)
+            };
+            builder.identity(identity.map_err(|e| invalid(cert, &e))?);
+        }
+        builder
+            .min_protocol_version(self.min_version.map(TlsVersion::protocol))
+            .danger_accept_invalid_certs(self.insecure)
+            .build()
+            .map_err(|e| CliError {
+                message: format!("could not set up TLS: {}", describe_error(&e)),
+                help: None,
+            })
+    }
+
+    /// The TLS config of a gRPC channel, which only supports CA certificates
+    /// and PEM client certificates.
+    pub fn grpc_config(&self) -> Result<tonic::transport::ClientTlsConfig, CliError> {
+        let unsupported = |setting: &str| CliError {
+            message: format!("{} is not supported for gRPC commands", setting),
+            help: None,
+        };
+        if self.insecure {
+            return Err(unsupported("--insecure"));
+        }
+        if self.min_version.is_some() {
+            return Err(unsupported("min_version"));
+        }
+        let mut config = tonic::transport::ClientTlsConfig::new().with_native_roots();
+        for path in &self.ca_certs {
+            config = config.ca_certificate(tonic::transport::Certificate::from_pem(read(path)?));
+        }
+        if let Some(cert) = &self.client_cert {
+            if is_pkcs12(cert) {
+                return Err(CliError {
+                    help: Some("use a PEM client_cert and client_key instead".to_string()),
+                    ..unsupported("a PKCS#12 client_cert")
+                });
+            }
+            let key = read(self.client_key(cert)?)?;
+            config = config.identity(tonic::transport::Identity::from_pem(read(cert)?, key));
+        }
+        Ok(config)
+    }
+}
+
+/// Summarizes a DER certificate as its subject, issuer and validity.
+pub fn describe_certificate(der: &[u8]) -> String {
+    match X509Certificate::from_der(der) {
+        Ok((_, cert)) => format!(
+            "{}, issued by {}, valid from {} to {}",
+            cert.subject(),
+            cert.issuer(),
+            cert.validity().not_before,
+            cert.validity().not_after
+        ),
+        Err(e) => format!("could not be parsed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("client.p12", true)]
+    #[case("certs/client.PFX", true)]
+    #[case("client.pem", false)]
+    #[case("client", false)]
+    fn test_is_pkcs12(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(is_pkcs12(Path::new(path)), expected);
+    }
+
+    #[rstest]
+    fn test_pem_client_cert_requires_key() {
+        let tls = Tls {
+            client_cert: Some(PathBuf::from("client.pem")),
+            ..Default::default()
+        };
+        let error = tls.connector().unwrap_err();
+        assert_eq!(
+            error.message,
+            "no client_key set for the client certificate client.pem"
+        );
+    }
+}
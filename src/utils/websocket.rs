@@ -0,0 +1,160 @@
+use crate::utils::error::CliError;
+use crate::utils::headers::Headers;
+use crate::utils::http::Response;
+use futures_util::{SinkExt, StreamExt};
+use native_tls::TlsConnector;
+use std::error::Error;
+use std::io::BufRead;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, USER_AGENT};
+use tokio_tungstenite::tungstenite::Message as Frame;
+use tokio_tungstenite::{connect_async_tls_with_config, Connector};
+
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+pub type MessageHandler<'a> = Box<dyn FnMut(&Message) -> bool + 'a>;
+
+/// A websocket session: `initial_message` is sent once connected, then every
+/// message received is passed to `on_message` until it returns false, the
+/// server closes the connection, `timeout` elapses or Ctrl-C is pressed.
+pub struct Session<'a> {
+    pub initial_message: Option<Message>,
+    /// Send the lines read from stdin as text messages
+    pub send_stdin: bool,
+    pub timeout: Option<Duration>,
+    pub on_message: MessageHandler<'a>,
+}
+
+/// How a session ended, along with the handshake response.
+pub struct SessionEnd {
+    pub handshake: Response,
+    /// The close code and reason, when the server closed the connection
+    pub closed_by_server: Option<String>,
+}
+
+pub fn is_websocket_url(url: &str) -> bool {
+    let url = url.to_lowercase();
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+fn read_stdin_lines() -> mpsc::UnboundedReceiver<String> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    // NOTE: stdin is read from a detached thread, since a blocking read would
+    // otherwise keep the runtime from shutting down
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+pub async fn run_session(
+    url: &str,
+    headers: &Headers,
+    connector: TlsConnector,
+    mut session: Session<'_>,
+) -> Result<SessionEnd, Box<dyn Error>> {
+    let connect_error = |e: &dyn std::fmt::Display| CliError {
+        message: format!("could not connect to {}: {}", url, e),
+        help: None,
+    };
+    let session_error = |e: &dyn std::fmt::Display| CliError {
+        message: format!("websocket session with {} failed: {}", url, e),
+        help: None,
+    };
+    let mut request = url.into_client_request().map_err(|e| connect_error(&e))?;
+    // NOTE: headers of the command replace those of the handshake
+    request.headers_mut().extend(headers.to_header_map()?);
+    request
+        .headers_mut()
+        .entry(USER_AGENT)
+        .or_insert(HeaderValue::from_static("hit-cli"));
+
+    let (mut socket, handshake) =
+        connect_async_tls_with_config(request, None, false, Some(Connector::NativeTls(connector)))
+            .await
+            .map_err(|e| connect_error(&e))?;
+    let handshake = Response {
+        url: url.to_string(),
+        status: handshake.status().as_u16(),
+        headers: Headers::from_header_map(handshake.headers()),
+        body: Vec::new(),
+    };
+
+    let initial_frame = match session.initial_message.take() {
+        Some(Message::Text(text)) => Some(Frame::text(text)),
+        Some(Message::Binary(data)) => Some(Frame::binary(data)),
+        None => None,
+    };
+    if let Some(frame) = initial_frame {
+        socket.send(frame).await.map_err(|e| session_error(&e))?;
+    }
+
+    let mut stdin_lines = session.send_stdin.then(read_stdin_lines);
+    let deadline = session.timeout.map(|timeout| Instant::now() + timeout);
+    let mut closed_by_server = None;
+    let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
+    loop {
+        tokio::select! {
+            frame = socket.next() => {
+                let message = match frame {
+                    Some(Ok(Frame::Text(text))) => Message::Text(text.to_string()),
+                    Some(Ok(Frame::Binary(data))) => Message::Binary(data.to_vec()),
+                    Some(Ok(Frame::Close(frame))) => {
+                        closed_by_server = Some(match frame {
+                            Some(frame) if frame.reason.is_empty() => u16::from(frame.code).to_string(),
+                            Some(frame) => format!("{} {}", u16::from(frame.code), frame.reason),
+                            None => "no status".to_string(),
+                        });
+                        break;
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(Box::new(session_error(&e))),
+                    None => break,
+                };
+                if !(session.on_message)(&message) {
+                    break;
+                }
+            }
+            line = async {
+                match &mut stdin_lines {
+                    Some(lines) => lines.recv().await,
+                    None => std::future::pending().await,
+                }
+            } => match line {
+                Some(line) => socket
+                    .send(Frame::text(line))
+                    .await
+                    .map_err(|e| session_error(&e))?,
+                None => stdin_lines = None,
+            },
+            _ = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            } => break,
+            _ = &mut ctrl_c => break,
+        }
+    }
+    if closed_by_server.is_none() {
+        // NOTE: the server may be gone already, in which case there is
+        // nothing left to close
+        let _ = socket.close(None).await;
+    }
+
+    Ok(SessionEnd {
+        handshake,
+        closed_by_server,
+    })
+}
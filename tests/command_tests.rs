@@ -0,0 +1,122 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{get_hit_command_for_setup, hit_setup, SetupFixture};
+use rstest::*;
+
+#[rstest]
+fn test_command_add(hit_setup: SetupFixture) {
+    let mut add_cmd = get_hit_command_for_setup(&hit_setup);
+    add_cmd.args([
+        "command",
+        "add",
+        "users/list",
+        "--method",
+        "get",
+        "--url",
+        "{{API_URL}}/users",
+    ]);
+    add_cmd.assert().success();
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["command", "list"]);
+    list_cmd.assert().success().stdout(
+        "├── get-by-id GET {{API_URL}}/items/:id\n\
+         └── users\n    \
+         └── list GET {{API_URL}}/users\n",
+    );
+}
+
+#[rstest]
+fn test_command_add_existing_fails(hit_setup: SetupFixture) {
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args([
+        "command",
+        "add",
+        "get-by-id",
+        "--method",
+        "GET",
+        "--url",
+        "{{API_URL}}/other",
+    ]);
+    cmd.assert().failure().stderr(
+        "command `get-by-id` already exists\nhelp: use `hit command edit get-by-id` to change it\n",
+    );
+}
+
+#[rstest]
+fn test_command_edit(hit_setup: SetupFixture) {
+    let mut edit_cmd = get_hit_command_for_setup(&hit_setup);
+    edit_cmd.args([
+        "command",
+        "edit",
+        "get-by-id",
+        "--method",
+        "DELETE",
+        "--header",
+        "X-Request-Id: :requestId",
+    ]);
+    edit_cmd.assert().success();
+
+    let mut show_cmd = get_hit_command_for_setup(&hit_setup);
+    show_cmd.args(["command", "show", "get-by-id"]);
+    let output = show_cmd.assert().success().get_output().stdout.clone();
+    let command: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(command["method"], "DELETE");
+    assert_eq!(command["url"], "{{API_URL}}/items/:id");
+    assert_eq!(command["headers"]["X-Request-Id"], ":requestId");
+}
+
+#[rstest]
+fn test_command_edit_body_type(hit_setup: SetupFixture) {
+    let mut edit_cmd = get_hit_command_for_setup(&hit_setup);
+    edit_cmd.args([
+        "command",
+        "edit",
+        "get-by-id",
+        "--method",
+        "PUT",
+        "--body-type",
+        "text",
+        "--body",
+        "hello :id",
+    ]);
+    edit_cmd.assert().success();
+
+    let mut show_cmd = get_hit_command_for_setup(&hit_setup);
+    show_cmd.args(["command", "show", "get-by-id"]);
+    let output = show_cmd.assert().success().get_output().stdout.clone();
+    let command: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(command["body_type"], "text");
+    assert_eq!(command["body"], "hello :id");
+}
+
+#[rstest]
+fn test_command_mv_and_rm(hit_setup: SetupFixture) {
+    let mut mv_cmd = get_hit_command_for_setup(&hit_setup);
+    mv_cmd.args(["command", "mv", "get-by-id", "items/get"]);
+    mv_cmd.assert().success();
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["command", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout("└── items\n    └── get GET {{API_URL}}/items/:id\n");
+
+    let mut rm_cmd = get_hit_command_for_setup(&hit_setup);
+    rm_cmd.args(["command", "rm", "items/get"]);
+    rm_cmd.assert().success();
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["command", "list"]);
+    list_cmd.assert().success().stdout("");
+}
+
+#[rstest]
+fn test_command_rm_unknown_fails(hit_setup: SetupFixture) {
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["command", "rm", "items/get"]);
+    cmd.assert()
+        .failure()
+        .stderr("command `items/get` not found\n");
+}
@@ -0,0 +1,184 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{
+    get_hit_command_for_dir, get_hit_command_for_setup, hit_setup, temp_dir, SetupFixture,
+};
+use predicates::prelude::*;
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+#[rstest]
+fn test_config_schema_matches_published_schema(temp_dir: TempDir) {
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["config", "schema"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let schema: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let published_schema: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("schema/config.schema.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(schema, published_schema);
+}
+
+#[rstest]
+fn test_new_config_references_schema(temp_dir: TempDir) {
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.arg("init");
+    cmd.assert().success();
+
+    let config_path = temp_dir.path().join(".hit").join("config.json");
+    let reader = fs::File::open(config_path).unwrap();
+    let hit_config: serde_json::Value = serde_json::from_reader(reader).unwrap();
+    assert_eq!(
+        hit_config["$schema"],
+        "https://raw.githubusercontent.com/meshde/hit-cli/master/schema/config.schema.json"
+    );
+}
+
+const UNORDERED_CONFIG: &str = r#"{
+  "envs": {
+    "prod": {
+      "ZETA": "z",
+      "ALPHA": "a"
+    },
+    "dev": {}
+  },
+  "commands": {
+    "users": {
+      "list": {
+        "url": "https://example.com/users",
+        "method": "GET",
+        "description": "List all users"
+      },
+      "create": {
+        "method": "POST",
+        "url": "https://example.com/users"
+      }
+    },
+    "health": {
+      "method": "GET",
+      "url": "https://example.com/health"
+    }
+  },
+  "x-owner": "api-team"
+}
+"#;
+
+#[rstest]
+fn test_saving_preserves_order_and_unknown_fields(hit_setup: SetupFixture) {
+    hit_setup.write_file(".hit/config.json", UNORDERED_CONFIG);
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["env", "set", "BETA", "b", "--env", "prod"]);
+    cmd.assert().success();
+
+    let config =
+        fs::read_to_string(hit_setup.temp_dir.path().join(".hit").join("config.json")).unwrap();
+    let expected = UNORDERED_CONFIG.replace(
+        "\"ALPHA\": \"a\"\n",
+        "\"ALPHA\": \"a\",\n      \"BETA\": \"b\"\n",
+    );
+    assert_eq!(config, expected);
+}
+
+#[rstest]
+fn test_config_fmt(hit_setup: SetupFixture) {
+    hit_setup.write_config(serde_json::from_str(UNORDERED_CONFIG).unwrap());
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["config", "fmt", "--check"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not formatted"))
+        .stderr(predicate::str::contains("help: run `hit config fmt`"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["config", "fmt"]);
+    cmd.assert().success();
+
+    let config: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(hit_setup.temp_dir.path().join(".hit").join("config.json")).unwrap(),
+    )
+    .unwrap();
+    let command_names: Vec<&String> = config["commands"].as_object().unwrap().keys().collect();
+    assert_eq!(command_names, ["users", "health"]);
+    assert_eq!(config["x-owner"], "api-team");
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["config", "fmt", "--check"]);
+    cmd.assert().success();
+}
+
+#[rstest]
+fn test_config_with_comments(hit_setup: SetupFixture) {
+    let config = r#"{
+  "envs": {
+    // shared with the mobile team
+    "prod": { "API_URL": "https://api.example.com" },
+  },
+  "commands": {
+    "get-by-id": {
+      "method": "GET",
+      "url": "{{API_URL}}/items/:id",
+      "headers": {
+        // required by the gateway, see the platform docs
+        "X-Team": "api",
+      },
+    },
+  },
+}
+"#;
+    hit_setup.write_file(".hit/config.json", config);
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args([
+        "command",
+        "add",
+        "health",
+        "--method",
+        "GET",
+        "--url",
+        "{{API_URL}}/health",
+    ]);
+    cmd.assert().success();
+
+    let saved =
+        fs::read_to_string(hit_setup.temp_dir.path().join(".hit").join("config.json")).unwrap();
+    assert!(saved.starts_with(&config[..config.rfind("    },\n  },").unwrap()]));
+    assert!(saved.contains("// shared with the mobile team"));
+    assert!(saved.contains("\"health\": {"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["command", "list"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("get-by-id"))
+        .stdout(predicate::str::contains("health"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["config", "fmt", "--check"]);
+    cmd.assert().failure();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["config", "fmt"]);
+    cmd.assert().success();
+
+    let formatted =
+        fs::read_to_string(hit_setup.temp_dir.path().join(".hit").join("config.json")).unwrap();
+    assert!(formatted.starts_with(
+        "{\n  \"envs\": {\n    // shared with the mobile team\n    \"prod\": {\n      \"API_URL\": \"https://api.example.com\"\n    }\n  },"
+    ));
+    assert!(formatted.contains(
+        "      \"headers\": {\n        // required by the gateway, see the platform docs\n        \"X-Team\": \"api\"\n      }"
+    ));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["config", "fmt", "--check"]);
+    cmd.assert().success();
+}
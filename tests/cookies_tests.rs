@@ -0,0 +1,94 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{get_hit_command_for_setup, hit_setup, MockServer, SetupFixture};
+use rstest::*;
+
+fn run(hit_setup: &SetupFixture, args: &[&str]) {
+    let mut cmd = get_hit_command_for_setup(hit_setup);
+    cmd.args(args);
+    cmd.assert().success();
+}
+
+fn sends_cookie(request: &str) -> bool {
+    request.contains("\r\ncookie: ")
+}
+
+#[rstest]
+fn test_cookie_jar(hit_setup: SetupFixture) {
+    let server = MockServer::start(
+        200,
+        &[
+            ("Content-Type", "application/json"),
+            ("Set-Cookie", "session=abc123; HttpOnly"),
+            ("Set-Cookie", "theme=dark; Path=/items"),
+        ],
+        b"{}",
+    );
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "prod": { "API_URL": server.url }, "dev": { "API_URL": server.url } },
+        "cookies": true,
+        "commands": {
+            "items": { "method": "GET", "url": "{{API_URL}}/items" },
+            "login": { "method": "GET", "url": "{{API_URL}}/login", "cookies": false }
+        }
+    }));
+
+    run(&hit_setup, &["run", "--env", "prod", "items"]);
+    run(&hit_setup, &["run", "--env", "prod", "items"]);
+    run(&hit_setup, &["run", "--env", "prod", "login"]);
+    run(
+        &hit_setup,
+        &["run", "--env", "prod", "--no-cookies", "items"],
+    );
+    run(&hit_setup, &["run", "--env", "dev", "items"]);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 5);
+    assert!(!sends_cookie(&requests[0]));
+    assert!(sends_cookie(&requests[1]));
+    assert!(requests[1].contains("session=abc123"));
+    assert!(requests[1].contains("theme=dark"));
+    assert!(!sends_cookie(&requests[2]));
+    assert!(!sends_cookie(&requests[3]));
+    assert!(!sends_cookie(&requests[4]));
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["cookies", "list", "--env", "prod", "--reveal"]);
+    list_cmd.assert().success().stdout(
+        "session=abc123 [env: prod] [domain: 127.0.0.1]\n\
+         theme=dark [env: prod] [domain: 127.0.0.1] [path: /items]\n",
+    );
+
+    run(&hit_setup, &["cookies", "clear", "--env", "prod"]);
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["cookies", "list"]);
+    list_cmd.assert().success().stdout(
+        "session=******** [env: dev] [domain: 127.0.0.1]\n\
+         theme=******** [env: dev] [domain: 127.0.0.1] [path: /items]\n",
+    );
+
+    run(&hit_setup, &["cookies", "clear"]);
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["cookies", "list"]);
+    list_cmd.assert().success().stdout("");
+}
+
+#[rstest]
+fn test_cookie_jar_is_opt_in(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[("Set-Cookie", "session=abc123")], b"{}");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "prod": { "API_URL": server.url } },
+        "commands": {
+            "items": { "method": "GET", "url": "{{API_URL}}/items" }
+        }
+    }));
+
+    run(&hit_setup, &["run", "--env", "prod", "items"]);
+    run(&hit_setup, &["run", "--env", "prod", "items"]);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(!sends_cookie(&requests[1]));
+}
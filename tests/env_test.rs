@@ -1,6 +1,7 @@
 mod fixtures;
 use assert_cmd::prelude::*;
-use fixtures::{get_hit_command_for_setup, hit_setup, SetupFixture};
+use fixtures::{get_hit_command_for_setup, hit_setup, MockServer, SetupFixture};
+use predicates::prelude::*;
 use rstest::*;
 
 #[rstest]
@@ -30,3 +31,230 @@ fn test_env_use(hit_setup: SetupFixture) -> Result<(), Box<dyn std::error::Error
 
     Ok(())
 }
+
+#[rstest]
+fn test_env_use_unknown_env(hit_setup: SetupFixture) {
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.args(["env", "use", "something"]);
+    use_cmd
+        .assert()
+        .failure()
+        .stderr("env `something` not found\nhelp: available envs: dev, prod, staging\n");
+}
+
+#[rstest]
+fn test_env_add_copy_rename_rm(hit_setup: SetupFixture) {
+    for args in [
+        ["env", "add", "local"].as_slice(),
+        ["env", "copy", "prod", "canary"].as_slice(),
+        ["env", "rename", "staging", "qa"].as_slice(),
+        ["env", "rm", "dev"].as_slice(),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["env", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout("   canary\n   local\n   prod\n   qa\n");
+
+    let mut show_cmd = get_hit_command_for_setup(&hit_setup);
+    show_cmd.args(["env", "show", "canary"]);
+    show_cmd
+        .assert()
+        .success()
+        .stdout("API_URL=https://api.example.com\n");
+}
+
+#[rstest]
+fn test_env_settings_follow_copy_rename_rm(hit_setup: SetupFixture) {
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": {}, "prod": {}, "staging": {} },
+        "commands": {},
+        "tls": { "dev": { "insecure": true }, "prod": { "ca_certs": ["ca.pem"] } },
+        "network": { "staging": { "proxy": "http://proxy:3128" } }
+    }));
+    for args in [
+        ["env", "copy", "prod", "canary"].as_slice(),
+        ["env", "rename", "staging", "qa"].as_slice(),
+        ["env", "rm", "dev"].as_slice(),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let config: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(hit_setup.temp_dir.path().join(".hit/config.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        config["tls"],
+        serde_json::json!({
+            "prod": { "ca_certs": ["ca.pem"] },
+            "canary": { "ca_certs": ["ca.pem"] }
+        })
+    );
+    assert_eq!(
+        config["network"],
+        serde_json::json!({ "qa": { "proxy": "http://proxy:3128" } })
+    );
+}
+
+#[rstest]
+fn test_env_settings_of_unknown_env(hit_setup: SetupFixture) {
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": {} },
+        "commands": {},
+        "network": { "prod": { "proxy": "http://proxy:3128" } }
+    }));
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["env", "list"]);
+    cmd.assert().failure().stderr(predicate::str::ends_with(
+        "config.json: `network` settings of unknown env `prod`\n\
+         help: add `prod` to `envs`, or remove its `network` settings\n",
+    ));
+}
+
+#[rstest]
+fn test_env_rename_active_env(hit_setup: SetupFixture) {
+    for args in [
+        ["env", "use", "dev"].as_slice(),
+        ["env", "rename", "dev", "local"].as_slice(),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["env", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout(" * local\n   prod\n   staging\n");
+}
+
+#[rstest]
+fn test_env_state_follows_rename_and_rm(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[("Set-Cookie", "session=abc123")], b"{}");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url }, "prod": { "API_URL": server.url } },
+        "cookies": true,
+        "commands": { "items": { "method": "GET", "url": "{{API_URL}}/items" } }
+    }));
+    let state = || -> serde_json::Value {
+        let text = std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+        serde_json::from_str(&text).unwrap()
+    };
+    let env_names = |state: &serde_json::Value, key: &str| -> Vec<String> {
+        state[key]
+            .as_object()
+            .into_iter()
+            .flat_map(|projects| projects.values())
+            .flat_map(|envs| envs.as_object().unwrap().keys().cloned())
+            .collect()
+    };
+
+    for args in [
+        ["env", "use", "dev"].as_slice(),
+        ["ephenv", "set", "TOKEN", "abc", "--env", "dev"].as_slice(),
+        ["run", "--env", "dev", "items"].as_slice(),
+        ["env", "rename", "dev", "local"].as_slice(),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let renamed = state();
+    for key in ["env_ephenvs", "env_prev_requests", "cookies"] {
+        assert_eq!(env_names(&renamed, key), vec!["local"], "{}", key);
+    }
+    let mut get_cmd = get_hit_command_for_setup(&hit_setup);
+    get_cmd.args(["ephenv", "get", "TOKEN", "--env", "local"]);
+    get_cmd.assert().success().stdout("abc\n");
+
+    let mut rm_cmd = get_hit_command_for_setup(&hit_setup);
+    rm_cmd.args(["env", "rm", "local"]);
+    rm_cmd.assert().success();
+
+    let removed = state();
+    for key in ["env_ephenvs", "env_prev_requests", "cookies"] {
+        assert!(env_names(&removed, key).is_empty(), "{}", key);
+    }
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["env", "list"]);
+    list_cmd.assert().success().stdout("   prod\n");
+}
+
+#[rstest]
+fn test_env_set_unset_and_show(hit_setup: SetupFixture) {
+    for args in [
+        ["env", "use", "dev"].as_slice(),
+        ["env", "set", "API_KEY", "dev-key"].as_slice(),
+        ["env", "set", "DEBUG", "true"].as_slice(),
+        ["env", "set", "DEBUG", "false", "--env", "prod"].as_slice(),
+        ["ephenv", "set", "AUTH_TOKEN", "abc", "--env", "dev"].as_slice(),
+        ["ephenv", "set", "USER", "meshde", "--env", "dev"].as_slice(),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let mut show_cmd = get_hit_command_for_setup(&hit_setup);
+    show_cmd.args(["env", "show"]);
+    show_cmd
+        .assert()
+        .success()
+        .stdout(
+            "API_KEY=********\nAPI_URL=https://dev-api.example.com\nAUTH_TOKEN=******** (ephenv)\nDEBUG=true\nUSER=meshde (ephenv)\n",
+        );
+
+    let mut reveal_cmd = get_hit_command_for_setup(&hit_setup);
+    reveal_cmd.args(["env", "show", "--reveal"]);
+    reveal_cmd
+        .assert()
+        .success()
+        .stdout(
+            "API_KEY=dev-key\nAPI_URL=https://dev-api.example.com\nAUTH_TOKEN=abc (ephenv)\nDEBUG=true\nUSER=meshde (ephenv)\n",
+        );
+
+    let mut unset_cmd = get_hit_command_for_setup(&hit_setup);
+    unset_cmd.args(["env", "unset", "API_KEY"]);
+    unset_cmd.assert().success();
+
+    let mut show_cmd = get_hit_command_for_setup(&hit_setup);
+    show_cmd.args(["env", "show"]);
+    show_cmd
+        .assert()
+        .success()
+        .stdout(
+            "API_URL=https://dev-api.example.com\nAUTH_TOKEN=******** (ephenv)\nDEBUG=true\nUSER=meshde (ephenv)\n",
+        );
+}
+
+#[rstest]
+fn test_env_diff(hit_setup: SetupFixture) {
+    for args in [
+        ["env", "set", "API_KEY", "dev-key", "--env", "dev"],
+        ["env", "set", "DEBUG", "true", "--env", "prod"],
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let mut diff_cmd = get_hit_command_for_setup(&hit_setup);
+    diff_cmd.args(["env", "diff", "dev", "prod"]);
+    diff_cmd.assert().success().stdout(
+        "- API_KEY: missing in prod\n\
+         ~ API_URL: https://dev-api.example.com → https://api.example.com\n\
+         + DEBUG: missing in dev\n",
+    );
+}
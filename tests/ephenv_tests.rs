@@ -0,0 +1,113 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{get_hit_command_for_setup, hit_setup, SetupFixture};
+use rstest::*;
+
+fn run_all(hit_setup: &SetupFixture, commands: &[&[&str]]) {
+    for args in commands {
+        let mut cmd = get_hit_command_for_setup(hit_setup);
+        cmd.args(*args);
+        cmd.assert().success();
+    }
+}
+
+#[rstest]
+fn test_ephenv_list(hit_setup: SetupFixture) {
+    run_all(
+        &hit_setup,
+        &[
+            &["ephenv", "set", "TOKEN", "abc"],
+            &["ephenv", "set", "TOKEN", "def", "--env", "prod"],
+            &["ephenv", "set", "API_KEY", "xyz"],
+        ],
+    );
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["ephenv", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout("API_KEY=********\nTOKEN=********\nTOKEN=******** [env: prod]\n");
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["ephenv", "list", "--reveal"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout("API_KEY=xyz\nTOKEN=abc\nTOKEN=def [env: prod]\n");
+}
+
+#[rstest]
+fn test_ephenv_get_prefers_env_scope(hit_setup: SetupFixture) {
+    run_all(
+        &hit_setup,
+        &[
+            &["env", "use", "prod"],
+            &["ephenv", "set", "TOKEN", "abc"],
+            &["ephenv", "set", "TOKEN", "def", "--env", "prod"],
+        ],
+    );
+
+    let mut get_cmd = get_hit_command_for_setup(&hit_setup);
+    get_cmd.args(["ephenv", "get", "TOKEN"]);
+    get_cmd.assert().success().stdout("def\n");
+
+    let mut get_cmd = get_hit_command_for_setup(&hit_setup);
+    get_cmd.args(["ephenv", "get", "TOKEN", "--env", "dev"]);
+    get_cmd.assert().success().stdout("abc\n");
+}
+
+#[rstest]
+fn test_ephenv_unset_and_clear(hit_setup: SetupFixture) {
+    run_all(
+        &hit_setup,
+        &[
+            &["ephenv", "set", "TOKEN", "abc"],
+            &["ephenv", "set", "USER", "meshde"],
+            &["ephenv", "set", "TOKEN", "def", "--env", "prod"],
+            &["ephenv", "unset", "TOKEN"],
+        ],
+    );
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["ephenv", "list", "--reveal"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout("USER=meshde\nTOKEN=def [env: prod]\n");
+
+    let mut unset_cmd = get_hit_command_for_setup(&hit_setup);
+    unset_cmd.args(["ephenv", "unset", "TOKEN"]);
+    unset_cmd
+        .assert()
+        .failure()
+        .stderr("ephenv `TOKEN` not set\n");
+
+    run_all(&hit_setup, &[&["ephenv", "clear"]]);
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["ephenv", "list"]);
+    list_cmd.assert().success().stdout("");
+}
+
+#[rstest]
+fn test_ephenv_ttl(hit_setup: SetupFixture) {
+    run_all(
+        &hit_setup,
+        &[
+            &["ephenv", "set", "TOKEN", "abc", "--ttl", "1h"],
+            &["ephenv", "set", "EXPIRED", "abc", "--ttl", "0s"],
+        ],
+    );
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["ephenv", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("TOKEN=******** [expires in "));
+
+    let mut get_cmd = get_hit_command_for_setup(&hit_setup);
+    get_cmd.args(["ephenv", "get", "EXPIRED"]);
+    get_cmd.assert().failure();
+}
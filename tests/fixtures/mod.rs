@@ -1,8 +1,28 @@
+#![allow(dead_code)]
+
 use assert_cmd::prelude::*;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+use prost::Message as _;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    MethodDescriptorProto, ServiceDescriptorProto,
+};
 use rstest::*;
 use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
 use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use tempfile::TempDir;
+use tonic::codegen::tokio_stream;
+use tonic::codegen::{http, Body, BoxFuture, BoxStream, Context, Poll, Service, StdError};
+use tonic::server::NamedService;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+use tonic::Status;
 
 #[derive(Debug)]
 pub struct SetupFixture {
@@ -39,6 +59,16 @@ impl SetupFixture {
 
         Self { temp_dir }
     }
+
+    pub fn write_config(&self, config: serde_json::Value) {
+        self.write_file(".hit/config.json", &config.to_string());
+    }
+
+    pub fn write_file(&self, path: &str, contents: &str) {
+        let path = self.temp_dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
 }
 
 #[fixture]
@@ -54,6 +84,7 @@ pub fn hit_setup(temp_dir: TempDir) -> SetupFixture {
 pub fn get_hit_command_for_dir(dir: &std::path::Path) -> Command {
     let mut cmd = Command::cargo_bin("hit-cli").expect("could not call hit-cli");
     cmd.current_dir(dir);
+    cmd.env_remove("HIT_ENV");
     return cmd;
 }
 
@@ -63,3 +94,325 @@ pub fn get_hit_command_for_setup(setup: &SetupFixture) -> Command {
     cmd.env("APP_CONFIG_DIR", app_config_dir);
     return cmd;
 }
+
+/// A minimal HTTP server that answers every request with the same response and
+/// records the raw requests it receives.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+fn mock_response(status: u16, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut response = format!("HTTP/1.1 {} OK\r\nConnection: close\r\n", status).into_bytes();
+    for (name, value) in headers {
+        response.extend(format!("{}: {}\r\n", name, value).into_bytes());
+    }
+    response.extend(format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes());
+    response.extend(body);
+    response
+}
+
+fn answer(mut stream: impl Read + Write, response: &[u8], requests: &Mutex<Vec<Vec<u8>>>) {
+    requests.lock().unwrap().push(read_request(&mut stream));
+    stream.write_all(response).unwrap();
+}
+
+impl MockServer {
+    pub fn start(status: u16, headers: &[(&str, &str)], body: &[u8]) -> Self {
+        Self::start_raw(mock_response(status, headers, body))
+    }
+
+    /// Like `start`, answering with the raw bytes of `response`, e.g. to send
+    /// header values that are not UTF-8.
+    pub fn start_raw(response: Vec<u8>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded_requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                answer(stream.unwrap(), &response, &recorded_requests);
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Like `start`, listening on the unix socket `path`, whose url is
+    /// `unix:<path>`.
+    #[cfg(unix)]
+    pub fn start_unix(path: &Path, status: u16, headers: &[(&str, &str)], body: &[u8]) -> Self {
+        let listener = std::os::unix::net::UnixListener::bind(path).unwrap();
+        let url = format!("unix:{}", path.display());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let response = mock_response(status, headers, body);
+        let recorded_requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                answer(stream.unwrap(), &response, &recorded_requests);
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| String::from_utf8_lossy(request).to_string())
+            .collect()
+    }
+}
+
+fn read_request(stream: &mut impl Read) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let read = stream.read(&mut buffer).unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        if read == 0 {
+            return request;
+        }
+        if let Some(headers_end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&request[..headers_end]).to_lowercase();
+            let content_length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |length| length.trim().parse().unwrap());
+            if request.len() >= headers_end + 4 + content_length {
+                return request;
+            }
+        }
+    }
+}
+
+/// An HTTPS server for localhost, whose certificate is issued by the CA in
+/// tests/fixtures/tls, that answers every request with `body`. With
+/// `require_client_cert`, clients must present a certificate issued by the
+/// same CA.
+pub struct TlsServer {
+    pub url: String,
+}
+
+impl TlsServer {
+    pub fn start(require_client_cert: bool, body: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "https://localhost:{}",
+            listener.local_addr().unwrap().port()
+        );
+        let certs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor
+            .set_private_key_file(certs.join("server-key.pem"), SslFiletype::PEM)
+            .unwrap();
+        acceptor
+            .set_certificate_chain_file(certs.join("server.pem"))
+            .unwrap();
+        if require_client_cert {
+            acceptor.set_ca_file(certs.join("ca.pem")).unwrap();
+            acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+        }
+        let acceptor = acceptor.build();
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                // NOTE: handshakes rejected by either side are expected
+                let Ok(mut stream) = acceptor.accept(stream.unwrap()) else {
+                    continue;
+                };
+                read_request(&mut stream);
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.shutdown();
+            }
+        });
+
+        Self { url }
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HelloRequest {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(int32, tag = "2")]
+    pub times: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HelloReply {
+    #[prost(string, tag = "1")]
+    pub message: String,
+}
+
+fn hello_descriptor_set() -> FileDescriptorSet {
+    let field = |name: &str, number: i32, r#type: Type| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        json_name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(r#type as i32),
+        ..Default::default()
+    };
+    let method = |name: &str, server_streaming: bool| MethodDescriptorProto {
+        name: Some(name.to_string()),
+        input_type: Some(".hello.HelloRequest".to_string()),
+        output_type: Some(".hello.HelloReply".to_string()),
+        server_streaming: Some(server_streaming),
+        ..Default::default()
+    };
+    FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("hello.proto".to_string()),
+            package: Some("hello".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("HelloRequest".to_string()),
+                    field: vec![
+                        field("name", 1, Type::String),
+                        field("times", 2, Type::Int32),
+                    ],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("HelloReply".to_string()),
+                    field: vec![field("message", 1, Type::String)],
+                    ..Default::default()
+                },
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Greeter".to_string()),
+                method: vec![method("SayHello", false), method("SayHellos", true)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+fn greet(request: &tonic::Request<HelloRequest>) -> Result<HelloReply, Status> {
+    let greeting = request
+        .metadata()
+        .get("x-greeting")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("Hello");
+    match request.get_ref().name.as_str() {
+        "" => Err(Status::invalid_argument("name is required")),
+        name => Ok(HelloReply {
+            message: format!("{}, {}!", greeting, name),
+        }),
+    }
+}
+
+struct SayHello;
+
+impl tonic::server::UnaryService<HelloRequest> for SayHello {
+    type Response = HelloReply;
+    type Future = BoxFuture<tonic::Response<HelloReply>, Status>;
+
+    fn call(&mut self, request: tonic::Request<HelloRequest>) -> Self::Future {
+        Box::pin(async move { greet(&request).map(tonic::Response::new) })
+    }
+}
+
+struct SayHellos;
+
+impl tonic::server::ServerStreamingService<HelloRequest> for SayHellos {
+    type Response = HelloReply;
+    type ResponseStream = BoxStream<HelloReply>;
+    type Future = BoxFuture<tonic::Response<BoxStream<HelloReply>>, Status>;
+
+    fn call(&mut self, request: tonic::Request<HelloRequest>) -> Self::Future {
+        Box::pin(async move {
+            let reply = greet(&request)?;
+            let replies = (0..request.get_ref().times).map(move |_| Ok(reply.clone()));
+            let stream: BoxStream<HelloReply> = Box::pin(tokio_stream::iter(replies));
+            Ok(tonic::Response::new(stream))
+        })
+    }
+}
+
+/// The `hello.Greeter` service, with a unary `SayHello` method and a server
+/// streaming `SayHellos` method.
+#[derive(Clone)]
+struct Greeter;
+
+impl NamedService for Greeter {
+    const NAME: &'static str = "hello.Greeter";
+}
+
+impl<B> Service<http::Request<B>> for Greeter
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(tonic_prost::ProstCodec::default());
+            Ok(match request.uri().path() {
+                "/hello.Greeter/SayHello" => grpc.unary(SayHello, request).await,
+                "/hello.Greeter/SayHellos" => grpc.server_streaming(SayHellos, request).await,
+                _ => Status::unimplemented("").into_http(),
+            })
+        })
+    }
+}
+
+/// An in-process gRPC server running the `hello.Greeter` service, with server
+/// reflection unless it is started without it.
+pub struct GrpcServer {
+    pub url: String,
+}
+
+impl GrpcServer {
+    pub fn start(reflection: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        listener.set_nonblocking(true).unwrap();
+
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let reflection_service = reflection.then(|| {
+                    tonic_reflection::server::Builder::configure()
+                        .register_file_descriptor_set(hello_descriptor_set())
+                        .build_v1()
+                        .unwrap()
+                });
+                Server::builder()
+                    .add_service(Greeter)
+                    .add_optional_service(reflection_service)
+                    .serve_with_incoming(TcpIncoming::from(listener))
+                    .await
+                    .unwrap();
+            });
+        });
+
+        Self { url }
+    }
+
+    /// Writes the descriptor set of the `hello.Greeter` service to `path`.
+    pub fn write_descriptor_set(path: &std::path::Path) {
+        fs::write(path, hello_descriptor_set().encode_to_vec()).unwrap();
+    }
+}
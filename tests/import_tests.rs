@@ -23,3 +23,68 @@ fn test_import_swagger(temp_dir: TempDir) {
     let hit_config: serde_json::Value = serde_json::from_reader(reader).unwrap();
     insta::assert_json_snapshot!(hit_config);
 }
+
+#[rstest]
+fn test_import_graphql_introspection(temp_dir: TempDir) {
+    fs::copy(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/introspection.json"),
+        temp_dir.path().join("introspection.json"),
+    )
+    .unwrap();
+
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["import", "--graphql", "./introspection.json"]);
+    cmd.assert().success();
+
+    let config_path = temp_dir.path().join(".hit").join("config.json");
+    let reader = fs::File::open(config_path).unwrap();
+    let hit_config: serde_json::Value = serde_json::from_reader(reader).unwrap();
+    insta::assert_json_snapshot!(hit_config);
+}
+
+#[rstest]
+fn test_import_reports_invalid_config(temp_dir: TempDir) {
+    fs::copy(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/swagger.yml"),
+        temp_dir.path().join("swagger.yml"),
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join(".hit")).unwrap();
+    let config_path = temp_dir.path().join(".hit").join("config.json");
+    fs::write(&config_path, "{ \"envs\": ").unwrap();
+
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["import", "./swagger.yml"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::starts_with("could not read "));
+
+    assert_eq!(fs::read_to_string(config_path).unwrap(), "{ \"envs\": ");
+}
+
+#[rstest]
+fn test_import_reports_unreadable_specs(temp_dir: TempDir) {
+    fs::write(temp_dir.path().join("broken.yml"), "openapi: [").unwrap();
+
+    for (args, error) in [
+        (
+            ["import", "missing.yml"].as_slice(),
+            "could not read missing.yml: ",
+        ),
+        (
+            ["import", "--graphql", "missing.graphql"].as_slice(),
+            "could not read missing.graphql: ",
+        ),
+        (
+            ["import", "broken.yml"].as_slice(),
+            "could not parse broken.yml as an OpenAPI spec: ",
+        ),
+    ] {
+        let mut cmd = get_hit_command_for_dir(temp_dir.path());
+        cmd.args(args);
+        cmd.assert()
+            .failure()
+            .stderr(predicates::str::starts_with(error));
+    }
+    assert!(!temp_dir.path().join(".hit").exists());
+}
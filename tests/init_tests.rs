@@ -0,0 +1,129 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{
+    get_hit_command_for_dir, get_hit_command_for_setup, hit_setup, temp_dir, SetupFixture,
+};
+use predicates::prelude::*;
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn read_config(dir: &std::path::Path) -> serde_json::Value {
+    let reader = fs::File::open(dir.join(".hit").join("config.json")).unwrap();
+    serde_json::from_reader(reader).unwrap()
+}
+
+#[rstest]
+fn test_commands_without_project_fail_with_help(temp_dir: TempDir) {
+    for args in [
+        vec!["env", "list"],
+        vec!["command", "list"],
+        vec!["run", "users"],
+    ] {
+        let mut cmd = get_hit_command_for_dir(temp_dir.path());
+        cmd.args(&args);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("no hit project found"))
+            .stderr(predicate::str::contains("help: run `hit init`"));
+    }
+    assert!(!temp_dir.path().join(".hit").exists());
+}
+
+#[rstest]
+fn test_init_blank(temp_dir: TempDir) {
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.arg("init");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Initialized hit project in"))
+        .stdout(predicate::str::contains(".hit/.env.local"));
+
+    assert!(temp_dir.path().join(".hit").join("postscripts").is_dir());
+    let config = read_config(temp_dir.path());
+    assert_eq!(config["envs"], serde_json::json!({}));
+    assert_eq!(config["commands"], serde_json::json!({}));
+}
+
+#[rstest]
+fn test_init_only_suggests_missing_gitignore_entries(temp_dir: TempDir) {
+    fs::write(temp_dir.path().join(".gitignore"), "target\n.hit/.env\n").unwrap();
+
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.arg("init");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("  .hit/.env.local"))
+        .stdout(predicate::str::contains("  .hit/.env\n").not());
+}
+
+#[rstest]
+fn test_init_example_template(temp_dir: TempDir) {
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["init", "--template", "example"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["command", "list"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("posts"))
+        .stdout(predicate::str::contains("create"));
+}
+
+#[rstest]
+fn test_init_from_openapi(temp_dir: TempDir) {
+    fs::copy(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/swagger.yml"),
+        temp_dir.path().join("swagger.yml"),
+    )
+    .unwrap();
+
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["init", "--openapi", "swagger.yml"]);
+    cmd.assert().success();
+
+    let config = read_config(temp_dir.path());
+    assert!(!config["commands"].as_object().unwrap().is_empty());
+}
+
+#[rstest]
+fn test_init_from_missing_openapi_spec(temp_dir: TempDir) {
+    let mut cmd = get_hit_command_for_dir(temp_dir.path());
+    cmd.args(["init", "--openapi", "missing.yml"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::starts_with("could not read missing.yml: "));
+
+    assert!(!temp_dir.path().join(".hit").exists());
+}
+
+#[rstest]
+fn test_init_fails_in_existing_project(hit_setup: SetupFixture) {
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.arg("init");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("a hit project already exists"));
+}
+
+#[rstest]
+fn test_import_merges_into_existing_project(hit_setup: SetupFixture) {
+    fs::copy(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/swagger.yml"),
+        hit_setup.temp_dir.path().join("swagger.yml"),
+    )
+    .unwrap();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["import", "swagger.yml"]);
+    cmd.assert().success();
+
+    let config = read_config(hit_setup.temp_dir.path());
+    let commands = config["commands"].as_object().unwrap();
+    assert!(commands.contains_key("get-by-id"));
+    assert!(commands.len() > 1);
+    assert!(config["envs"]["dev"].is_object());
+    assert_eq!(config["envs"]["prod"]["API_URL"], "https://api.example.com");
+}
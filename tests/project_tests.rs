@@ -0,0 +1,47 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{get_hit_command_for_setup, hit_setup, SetupFixture};
+use rstest::*;
+use std::fs;
+
+#[rstest]
+fn test_project_discovered_from_subdirectory(hit_setup: SetupFixture) {
+    let sub_dir = hit_setup.temp_dir.path().join("src").join("nested");
+    fs::create_dir_all(&sub_dir).unwrap();
+
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.current_dir(&sub_dir);
+    use_cmd.args(["env", "use", "prod"]);
+    use_cmd.assert().success();
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["env", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout("   dev\n * prod\n   staging\n");
+
+    assert!(!sub_dir.join(".hit").exists());
+}
+
+#[rstest]
+fn test_project_dir_overrides(hit_setup: SetupFixture) {
+    let other_dir = tempfile::TempDir::new().unwrap();
+
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.current_dir(other_dir.path());
+    use_cmd.args(["env", "use", "dev", "--project-dir"]);
+    use_cmd.arg(hit_setup.temp_dir.path());
+    use_cmd.assert().success();
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.current_dir(other_dir.path());
+    list_cmd.env("HIT_PROJECT_DIR", hit_setup.temp_dir.path());
+    list_cmd.args(["env", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout(" * dev\n   prod\n   staging\n");
+
+    assert!(!other_dir.path().join(".hit").exists());
+}
@@ -1,6 +1,9 @@
 mod fixtures;
 use assert_cmd::prelude::*;
-use fixtures::{get_hit_command_for_setup, hit_setup, SetupFixture};
+use fixtures::{
+    get_hit_command_for_setup, hit_setup, GrpcServer, MockServer, SetupFixture, TlsServer,
+};
+use predicates::prelude::*;
 use rstest::*;
 
 #[rstest]
@@ -17,10 +20,1171 @@ fn test_failure_when_env_not_set(
 #[rstest]
 fn test_failure_when_env_not_recognized(hit_setup: SetupFixture) -> () {
     let mut use_cmd = get_hit_command_for_setup(&hit_setup);
-    use_cmd.args(["env", "use", "something"]);
+    use_cmd.args(["env", "use", "staging"]);
     use_cmd.assert().success();
 
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": "https://dev-api.example.com" } },
+        "commands": {
+            "get-by-id": { "method": "GET", "url": "{{API_URL}}/items/:id" }
+        }
+    }));
+
     let mut cmd = get_hit_command_for_setup(&hit_setup);
     cmd.args(["run", "get-by-id", "--id", "meshde"]);
     cmd.assert().failure().stderr("env not recognized\n");
 }
+
+#[rstest]
+fn test_verbose_shows_ephenvs(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[("Content-Type", "application/json")], b"{}");
+    for args in [
+        ["env", "use", "dev"].as_slice(),
+        ["env", "set", "API_URL", &server.url].as_slice(),
+        ["env", "set", "TOKEN", "env-token"].as_slice(),
+        ["ephenv", "set", "TOKEN", "dev-token", "--env", "dev"].as_slice(),
+        ["ephenv", "set", "USER", "meshde"].as_slice(),
+        ["ephenv", "set", "OTHER", "prod-only", "--env", "prod"].as_slice(),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().success();
+    }
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--verbose", "get-by-id", "--id", "meshde"]);
+    cmd.assert().success().stderr(
+        "variable TOKEN (ephenv scoped to dev, overriding env dev)\n\
+         variable USER (ephenv)\n",
+    );
+    assert!(server.requests()[0].starts_with("GET /items/meshde HTTP/1.1"));
+}
+
+fn secrets_config(api_url: &str) -> serde_json::Value {
+    serde_json::json!({
+        "envs": {
+            "dev": {
+                "API_URL": api_url,
+                "API_KEY": {"from_env": "HIT_TEST_API_KEY"},
+                "DOTENV_KEY": {"from_env": "HIT_TEST_DOTENV_KEY"},
+                "FILE_KEY": {"from_file": "secret.txt"},
+                "COMMAND_KEY": {"command": "echo command-secret"},
+                "UNUSED_KEY": {"command": "exit 1"}
+            }
+        },
+        "commands": {
+            "login": {
+                "method": "POST",
+                "url": "{{API_URL}}/login?key={{API_KEY}}",
+                "headers": {
+                    "X-Dotenv-Key": "{{DOTENV_KEY}}",
+                    "X-File-Key": "{{FILE_KEY}}"
+                },
+                "body": {"key": "{{COMMAND_KEY}}"}
+            }
+        }
+    })
+}
+
+#[rstest]
+fn test_dry_run_masks_secrets(hit_setup: SetupFixture) {
+    hit_setup.write_config(secrets_config("https://api.example.com"));
+    hit_setup.write_file(".hit/.env", "HIT_TEST_DOTENV_KEY=dotenv-secret\n");
+    hit_setup.write_file("secret.txt", "file-secret\n");
+
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.args(["env", "use", "dev"]);
+    use_cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("HIT_TEST_API_KEY", "env-secret");
+    cmd.args(["run", "--dry-run", "login"]);
+    cmd.assert().success().stdout(
+        "POST https://api.example.com/login?key=********\n\
+         X-Dotenv-Key: ********\n\
+         X-File-Key: ********\n\
+         \n\
+         {\n  \"key\": \"********\"\n}\n",
+    );
+}
+
+#[rstest]
+fn test_dry_run_masks_env_helper_values(hit_setup: SetupFixture) {
+    hit_setup.write_config(serde_json::json!({
+        "envs": {
+            "dev": {
+                "API_URL": "https://api.example.com",
+                "name": {"from_env": "HIT_TEST_NAME"}
+            }
+        },
+        "commands": {
+            "me": {
+                "method": "GET",
+                "url": "{{API_URL}}/users/{{name}}",
+                "headers": { "Authorization": "Bearer {{env \"HIT_TEST_TOKEN\"}}" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("HIT_TEST_TOKEN", "supersecret");
+    cmd.env("HIT_TEST_NAME", "ada");
+    cmd.args(["run", "--env", "dev", "--dry-run", "me"]);
+    cmd.assert().success().stdout(
+        "GET https://api.example.com/users/********\n\
+         Authorization: Bearer ********\n",
+    );
+}
+
+#[rstest]
+fn test_missing_secret_reference(hit_setup: SetupFixture) {
+    hit_setup.write_config(secrets_config("https://api.example.com"));
+    hit_setup.write_file(".hit/.env", "HIT_TEST_DOTENV_KEY=dotenv-secret\n");
+    hit_setup.write_file("secret.txt", "file-secret\n");
+
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.args(["env", "use", "dev"]);
+    use_cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env_remove("HIT_TEST_API_KEY");
+    cmd.args(["run", "--dry-run", "login"]);
+    cmd.assert().failure().stderr(
+        "environment variable `HIT_TEST_API_KEY` used by `API_KEY` is not set\n\
+         help: export it or add it to .hit/.env\n",
+    );
+}
+
+#[rstest]
+fn test_history_masks_secrets(hit_setup: SetupFixture) {
+    let server = MockServer::start(
+        200,
+        &[("Content-Type", "application/json")],
+        b"{\"echo\": \"env-secret\"}",
+    );
+    hit_setup.write_config(secrets_config(&server.url));
+    hit_setup.write_file(".hit/.env", "HIT_TEST_DOTENV_KEY=dotenv-secret\n");
+    hit_setup.write_file("secret.txt", "file-secret\n");
+
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.args(["env", "use", "dev"]);
+    use_cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("HIT_TEST_API_KEY", "env-secret");
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "login"]);
+    cmd.assert().success();
+
+    let request = &server.requests()[0];
+    assert!(request.starts_with("POST /login?key=env-secret HTTP/1.1"));
+    assert!(request.contains("x-dotenv-key: dotenv-secret"));
+    assert!(request.contains("x-file-key: file-secret"));
+    assert!(request.ends_with("{\"key\":\"command-secret\"}"));
+
+    let app_config =
+        std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(!app_config.contains("env-secret"));
+    assert!(app_config.contains("/login?key=********"));
+}
+
+#[rstest]
+fn test_env_override(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": {
+            "dev": { "API_URL": format!("{}/dev", server.url) },
+            "prod": { "API_URL": format!("{}/prod", server.url) },
+            "staging": { "API_URL": format!("{}/staging", server.url) }
+        },
+        "commands": {
+            "ping": { "method": "GET", "url": "{{API_URL}}/ping" }
+        }
+    }));
+
+    let mut use_cmd = get_hit_command_for_setup(&hit_setup);
+    use_cmd.args(["env", "use", "dev"]);
+    use_cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "prod", "ping"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("HIT_ENV", "staging");
+    cmd.args(["run", "ping"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("HIT_ENV", "staging");
+    cmd.args(["run", "--env", "prod", "ping"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "ping"]);
+    cmd.assert().success();
+
+    let paths: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|request| request.lines().next().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "GET /prod/ping HTTP/1.1",
+            "GET /staging/ping HTTP/1.1",
+            "GET /prod/ping HTTP/1.1",
+            "GET /dev/ping HTTP/1.1",
+        ]
+    );
+
+    let mut list_cmd = get_hit_command_for_setup(&hit_setup);
+    list_cmd.args(["env", "list"]);
+    list_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" * dev"));
+}
+
+#[rstest]
+fn test_last_is_scoped_to_env(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": {
+            "dev": { "API_URL": server.url },
+            "prod": { "API_URL": server.url }
+        },
+        "commands": {
+            "ping": { "method": "GET", "url": "{{API_URL}}/ping" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "prod", "ping"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("HIT_ENV", "dev");
+    cmd.args(["last", "view"]);
+    cmd.assert()
+        .failure()
+        .stderr("no last request found in env `dev`\n");
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["last", "view", "--env", "dev"]);
+    cmd.assert()
+        .failure()
+        .stderr("no last request found in env `dev`\n");
+}
+
+#[rstest]
+fn test_params_are_substituted_safely(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "update": {
+                "method": "POST",
+                "url": "{{API_URL}}/users/:id/:idType?q=:q",
+                "body": {
+                    "name": ":name",
+                    "at": "12::30"
+                }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("EDITOR", "true");
+    cmd.args([
+        "run",
+        "--env",
+        "dev",
+        "update",
+        "--id",
+        "a b/c",
+        "--id-type",
+        "uuid",
+        "--q",
+        "x&y=:id",
+        "--name",
+        "say \"hi\" to :id",
+    ]);
+    cmd.assert().success();
+
+    let request = &server.requests()[0];
+    assert!(request.starts_with("POST /users/a%20b%2Fc/uuid?q=x%26y%3D:id HTTP/1.1"));
+    assert!(request.ends_with(r#"{"name":"say \"hi\" to :id","at":"12::30"}"#));
+}
+
+#[rstest]
+fn test_params_named_like_run_options(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "logs": { "method": "GET", "url": "{{API_URL}}/logs?verbose=:verbose" },
+            "wait": { "method": "GET", "url": "{{API_URL}}/wait/:timeout" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "logs", "--verbose", "2"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "wait", "--timeout", "30"]);
+    cmd.assert().success();
+
+    let requests = server.requests();
+    assert!(requests[0].starts_with("GET /logs?verbose=2 HTTP/1.1"));
+    assert!(requests[1].starts_with("GET /wait/30 HTTP/1.1"));
+}
+
+#[rstest]
+fn test_params_named_like_env_options(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "deploys": {
+                "method": "GET",
+                "url": "{{API_URL}}/deploys/:env?proxy=:proxy&insecure=:insecure"
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args([
+        "run",
+        "--env",
+        "dev",
+        "deploys",
+        "--env",
+        "prod",
+        "--proxy",
+        "edge",
+        "--insecure",
+        "no",
+    ]);
+    cmd.assert().success();
+
+    assert!(server.requests()[0].starts_with("GET /deploys/prod?proxy=edge&insecure=no HTTP/1.1"));
+}
+
+#[rstest]
+fn test_undefined_variables(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url, "TEAM": "a&b" } },
+        "commands": {
+            "get": {
+                "method": "GET",
+                "url": "{{API_URL}}/teams/{{TEAM}}{{SUFFIX}}",
+                "headers": { "Authorization": "Bearer {{TOKEN}}" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "get"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "undefined variables `SUFFIX`, `TOKEN`",
+        ))
+        .stderr(predicate::str::contains("env `dev`"));
+    assert!(server.requests().is_empty());
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "--lenient", "get"]);
+    cmd.assert().success();
+
+    let request = &server.requests()[0];
+    assert!(request.starts_with("GET /teams/a&b HTTP/1.1"));
+    assert!(request
+        .to_lowercase()
+        .contains("authorization: bearer \r\n"));
+}
+
+#[rstest]
+fn test_template_helpers(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url, "CREDENTIALS": "user:pass" } },
+        "commands": {
+            "create": {
+                "method": "POST",
+                "url": "{{API_URL}}/users",
+                "headers": {
+                    "Authorization": "Basic {{base64 CREDENTIALS}}",
+                    "Idempotency-Key": "{{uuid}}"
+                },
+                "body": { "email": "{{email}}" }
+            }
+        }
+    }));
+
+    for _ in 0..2 {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.env("EDITOR", "true");
+        cmd.env("HIT_SEED", "42");
+        cmd.args(["run", "--env", "dev", "create"]);
+        cmd.assert().success();
+    }
+
+    let requests = server.requests();
+    assert!(requests[0]
+        .to_lowercase()
+        .contains("authorization: basic dxnlcjpwyxnz\r\n"));
+    assert!(requests[0].contains("@example.com\"}"));
+    assert_eq!(requests[0], requests[1]);
+}
+
+#[rstest]
+fn test_form_body(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "token": {
+                "method": "POST",
+                "url": "{{API_URL}}/token",
+                "body_type": "form",
+                "body": { "grant_type": "password", "username": ":user" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "token", "--user", "a&b c"]);
+    cmd.assert().success();
+
+    let request = server.requests()[0].to_lowercase();
+    assert!(request.contains("content-type: application/x-www-form-urlencoded\r\n"));
+    assert!(request.ends_with("grant_type=password&username=a%26b+c"));
+}
+
+#[rstest]
+fn test_multipart_body(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_file("me.png", "not really a png");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "upload": {
+                "method": "POST",
+                "url": "{{API_URL}}/avatar",
+                "headers": { "Content-Type": "application/json" },
+                "body_type": "multipart",
+                "body": { "name": ":name", "avatar": ":avatar" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("EDITOR", "true");
+    cmd.args([
+        "run", "--env", "dev", "upload", "--name", "@@me", "--avatar", "@me.png",
+    ]);
+    cmd.assert().success();
+
+    let request = &server.requests()[0];
+    let lowercase = request.to_lowercase();
+    assert!(lowercase.contains("content-type: multipart/form-data; boundary="));
+    assert!(!lowercase.contains("application/json"));
+    assert!(request.contains("name=\"name\"\r\n\r\n@me\r\n"));
+    assert!(request.contains("name=\"avatar\"; filename=\"me.png\"\r\nContent-Type: image/png\r\n\r\nnot really a png\r\n"));
+}
+
+#[rstest]
+fn test_binary_body(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_file("data.bin", "raw bytes");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "upload": {
+                "method": "PUT",
+                "url": "{{API_URL}}/blob",
+                "body_type": "binary",
+                "body": ":file"
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "upload", "--file", "data.bin"]);
+    cmd.assert().success();
+
+    let request = server.requests()[0].to_lowercase();
+    assert!(request.contains("content-type: application/octet-stream\r\n"));
+    assert!(request.ends_with("\r\n\r\nraw bytes"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "upload", "--file", "missing.bin"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("could not read missing.bin"));
+}
+
+#[rstest]
+fn test_file_paths_of_config_and_params(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"ok");
+    hit_setup.write_file("assets/logo.png", "project logo");
+    hit_setup.write_file("assets/data.bin", "project bytes");
+    hit_setup.write_file("sub/me.png", "my avatar");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "upload": {
+                "method": "POST",
+                "url": "{{API_URL}}/avatar",
+                "body_type": "multipart",
+                "body": { "logo": "@assets/logo.png", "avatar": ":avatar" }
+            },
+            "put-blob": {
+                "method": "PUT",
+                "url": "{{API_URL}}/blob",
+                "body_type": "binary",
+                "body": "@assets/data.bin"
+            }
+        }
+    }));
+    let sub_dir = hit_setup.temp_dir.path().join("sub");
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.current_dir(&sub_dir);
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "upload", "--avatar", "@me.png"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.current_dir(&sub_dir);
+    cmd.args(["run", "--env", "dev", "put-blob"]);
+    cmd.assert().success();
+
+    let requests = server.requests();
+    assert!(requests[0]
+        .contains("filename=\"logo.png\"\r\nContent-Type: image/png\r\n\r\nproject logo\r\n"));
+    assert!(
+        requests[0].contains("filename=\"me.png\"\r\nContent-Type: image/png\r\n\r\nmy avatar\r\n")
+    );
+    assert!(requests[1].ends_with("\r\n\r\nproject bytes"));
+}
+
+#[rstest]
+fn test_binary_response(hit_setup: SetupFixture) {
+    let body: &[u8] = &[0x89, b'P', b'N', b'G', 0, 0xff, 0xfe];
+    let server = MockServer::start(200, &[("Content-Type", "image/png")], body);
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": { "avatar": { "method": "GET", "url": "{{API_URL}}/avatar.png" } }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "avatar"]);
+    cmd.assert().success().stdout(body);
+
+    let state = std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(state.contains("\"reason\": \"binary\""));
+    assert!(state.contains("\"content_type\": \"image/png\""));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "-o", "avatar.png", "avatar"]);
+    cmd.assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains("saved 7 B to avatar.png"));
+    assert_eq!(
+        std::fs::read(hit_setup.temp_dir.path().join("avatar.png")).unwrap(),
+        body
+    );
+
+    let state = std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(state.contains("\"reason\": \"saved_to_file\""));
+}
+
+#[rstest]
+fn test_server_sent_events(hit_setup: SetupFixture) {
+    let server = MockServer::start(
+        200,
+        &[("Content-Type", "text/event-stream")],
+        b": hello\n\nevent: tick\ndata: {\"n\":1}\n\ndata: plain\n\ndata: {\"n\":3}\n\n",
+    );
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": { "events": { "method": "GET", "url": "{{API_URL}}/events" } }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["run", "--env", "dev", "--max-events", "2", "events"]);
+    cmd.assert()
+        .success()
+        .stdout("event: tick\n{\n  \"n\": 1\n}\nplain\n");
+
+    let state = std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(state.contains("\"reason\": \"streamed\""));
+}
+
+#[rstest]
+fn test_stream_command(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"{\"n\":1}\n{\"n\":2}");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "lines": { "method": "GET", "url": "{{API_URL}}/lines", "stream": true }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["run", "--env", "dev", "lines"]);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"n\": 1\n}\n{\n  \"n\": 2\n}\n");
+}
+
+#[rstest]
+fn test_websocket_command(hit_setup: SetupFixture) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        let received = socket.read().unwrap().into_text().unwrap().to_string();
+        socket.send("{\"n\":1}".into()).unwrap();
+        socket.send("plain".into()).unwrap();
+        socket.send("{\"n\":3}".into()).unwrap();
+        received
+    });
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "WS_URL": url } },
+        "commands": {
+            "subscribe": {
+                "method": "GET",
+                "url": "{{WS_URL}}/feed",
+                "body": { "subscribe": "{{WS_URL}}" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "--max-events", "2", "subscribe"]);
+    cmd.assert().success().stdout("{\n  \"n\": 1\n}\nplain\n");
+
+    assert_eq!(
+        server.join().unwrap(),
+        format!("{{\"subscribe\":\"{}\"}}", url)
+    );
+    let state = std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(state.contains("\"reason\": \"websocket\""));
+}
+
+#[rstest]
+fn test_websocket_connection_reset(hit_setup: SetupFixture) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        socket.send("{\"n\":1}".into()).unwrap();
+        // NOTE: dropping the socket closes the connection without a close frame
+    });
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "WS_URL": url } },
+        "commands": {
+            "subscribe": { "method": "GET", "url": "{{WS_URL}}/feed" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["run", "--env", "dev", "--ws-timeout", "10s", "subscribe"]);
+    cmd.assert()
+        .failure()
+        .stdout("{\n  \"n\": 1\n}\n")
+        .stderr(predicate::str::starts_with(format!(
+            "websocket session with {}/feed failed: ",
+            url
+        )));
+}
+
+#[rstest]
+fn test_graphql_command(hit_setup: SetupFixture) {
+    let server = MockServer::start(
+        200,
+        &[("Content-Type", "application/json")],
+        b"{\"data\":{\"user\":{\"name\":\"Ada\"}}}",
+    );
+    hit_setup.write_file(
+        "queries/user.graphql",
+        "query User($id: ID!, $first: Int, $locale: String) {\n  user(id: $id) { name }\n}\n",
+    );
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "user": {
+                "method": "POST",
+                "url": "{{API_URL}}/graphql",
+                "graphql": "@queries/user.graphql",
+                "body": { "locale": "en" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "user", "--id", "42", "--first", "5"]);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"user\": {\n    \"name\": \"Ada\"\n  }\n}\n");
+
+    let request = server.requests()[0].clone();
+    let body: serde_json::Value =
+        serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "query": "query User($id: ID!, $first: Int, $locale: String) {\n  user(id: $id) { name }\n}\n",
+            "variables": { "locale": "en", "id": "42", "first": 5 }
+        })
+    );
+}
+
+#[rstest]
+fn test_graphql_errors(hit_setup: SetupFixture) {
+    let server = MockServer::start(
+        200,
+        &[("Content-Type", "application/json")],
+        b"{\"data\":null,\"errors\":[{\"message\":\"not found\",\"path\":[\"user\"]}]}",
+    );
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "user": {
+                "method": "POST",
+                "url": "{{API_URL}}/graphql",
+                "graphql": "query User($id: ID!) { user(id: $id) { name } }"
+            }
+        }
+    }));
+
+    let mut missing = get_hit_command_for_setup(&hit_setup);
+    missing.args(["run", "--env", "dev", "user"]);
+    missing
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--id <ID!>"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "user", "--id", "7"]);
+    cmd.assert()
+        .failure()
+        .stdout("")
+        .stderr("the GraphQL request failed:\n  not found (at user)\n");
+}
+
+#[rstest]
+fn test_grpc_command(hit_setup: SetupFixture) {
+    let server = GrpcServer::start(true);
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "GRPC_URL": server.url } },
+        "commands": {
+            "hello": {
+                "method": "POST",
+                "url": "{{GRPC_URL}}",
+                "headers": { "x-greeting": "Hi" },
+                "grpc": { "service": "hello.Greeter", "method": "SayHello" },
+                "body": { "name": ":name" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "hello", "--name", "Ada"]);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"message\": \"Hi, Ada!\"\n}\n");
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "hello", "--name", ""]);
+    cmd.assert()
+        .failure()
+        .stderr("gRPC call failed with InvalidArgument: name is required\n");
+}
+
+#[rstest]
+fn test_grpc_streaming_with_descriptor_set(hit_setup: SetupFixture) {
+    let server = GrpcServer::start(false);
+    GrpcServer::write_descriptor_set(&hit_setup.temp_dir.path().join("hello.protoset"));
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "GRPC_URL": server.url } },
+        "commands": {
+            "hellos": {
+                "method": "POST",
+                "url": "{{GRPC_URL}}",
+                "grpc": {
+                    "service": "hello.Greeter",
+                    "method": "SayHellos",
+                    "protos": ["hello.protoset"]
+                },
+                "body": { "name": "Ada", "times": 3 }
+            },
+            "reflected": {
+                "method": "POST",
+                "url": "{{GRPC_URL}}",
+                "grpc": { "service": "hello.Greeter", "method": "SayHellos" }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.env("EDITOR", "true");
+    cmd.args(["run", "--env", "dev", "--max-events", "2", "hellos"]);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"message\": \"Hello, Ada!\"\n}\n{\n  \"message\": \"Hello, Ada!\"\n}\n");
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "reflected"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "could not resolve hello.Greeter with server reflection",
+    ));
+}
+
+/// The path of a file under tests/fixtures/tls, which configs can use as is.
+fn tls_fixture(name: &str) -> String {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/tls")
+        .join(name)
+        .display()
+        .to_string()
+}
+
+#[rstest]
+fn test_tls_ca_certs(hit_setup: SetupFixture) {
+    let server = TlsServer::start(false, "{\"ok\":true}");
+    let mut config = serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "health": { "method": "GET", "url": "{{API_URL}}/health" }
+        }
+    });
+    hit_setup.write_config(config.clone());
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "health"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("certificate verify failed"))
+        .stderr(predicate::str::contains(
+            "help: trust its CA with `ca_certs` in the `tls` settings of the command or env, or pass --insecure to skip verification",
+        ));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["run", "--env", "dev", "--insecure", "health"]);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"ok\": true\n}\n")
+        .stderr(predicate::str::starts_with(
+            "warning: --insecure skips verifying the TLS certificate of the server",
+        ));
+
+    config["tls"] = serde_json::json!({ "dev": { "ca_certs": [tls_fixture("ca.pem")] } });
+    hit_setup.write_config(config);
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["run", "--env", "dev", "--verbose", "health"]);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"ok\": true\n}\n")
+        .stderr(predicate::str::contains("protocol HTTP/1.1 over TLS\n"))
+        .stderr(predicate::str::contains(
+            "certificate CN=localhost, issued by CN=hit test CA, valid from ",
+        ));
+}
+
+#[rstest]
+fn test_tls_client_certificates(hit_setup: SetupFixture) {
+    let server = TlsServer::start(true, "{\"ok\":true}");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url, "CERT_PASSWORD": "secret" } },
+        "tls": { "dev": { "ca_certs": [tls_fixture("ca.pem")] } },
+        "commands": {
+            "anonymous": { "method": "GET", "url": "{{API_URL}}/me" },
+            "pem": {
+                "method": "GET",
+                "url": "{{API_URL}}/me",
+                "tls": {
+                    "client_cert": tls_fixture("client.pem"),
+                    "client_key": tls_fixture("client-key.pem")
+                }
+            },
+            "pkcs12": {
+                "method": "GET",
+                "url": "{{API_URL}}/me",
+                "tls": {
+                    "client_cert": tls_fixture("client.p12"),
+                    "client_cert_password": "{{CERT_PASSWORD}}"
+                }
+            }
+        }
+    }));
+
+    for command in ["pem", "pkcs12"] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.env("NO_COLOR", "1");
+        cmd.args(["run", "--env", "dev", command]);
+        cmd.assert().success().stdout("{\n  \"ok\": true\n}\n");
+    }
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "anonymous"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::starts_with("error sending request for url"));
+}
+
+#[rstest]
+fn test_resolve_overrides(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[("Content-Type", "application/json")], b"{}");
+    let port = server.url.rsplit(':').next().unwrap().to_string();
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "canary": { "API_URL": format!("http://api.example.test:{}", port) } },
+        "network": {
+            "canary": {
+                "resolve": [
+                    "api.example.test:1:192.0.2.1",
+                    format!("api.example.test:{}:127.0.0.1", port)
+                ]
+            }
+        },
+        "commands": {
+            "items": { "method": "GET", "url": "{{API_URL}}/items" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "canary", "items"]);
+    cmd.assert().success();
+    let request = &server.requests()[0];
+    assert!(request.starts_with("GET /items HTTP/1.1\r\n"));
+    assert!(request.contains(&format!("\r\nhost: api.example.test:{}\r\n", port)));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args([
+        "run",
+        "--env",
+        "canary",
+        "--resolve",
+        "api.example.test",
+        "items",
+    ]);
+    cmd.assert().failure().stderr(
+        "invalid resolve entry `api.example.test`\n\
+         help: use HOST:PORT:ADDRESS, e.g. example.com:443:127.0.0.1\n",
+    );
+}
+
+#[rstest]
+fn test_proxy(hit_setup: SetupFixture) {
+    let proxy = MockServer::start(200, &[("Content-Type", "application/json")], b"{}");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "corp": { "API_URL": "http://api.example.test", "PROXY_URL": proxy.url } },
+        "network": { "corp": { "proxy": "{{PROXY_URL}}" } },
+        "commands": {
+            "items": { "method": "GET", "url": "{{API_URL}}/items" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "corp", "items"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "corp", "--proxy", "not a url", "items"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::starts_with("invalid proxy `not a url`"));
+
+    let requests = proxy.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("GET http://api.example.test/items HTTP/1.1\r\n"));
+}
+
+#[rstest]
+fn test_network_settings_unsupported_for_websocket_and_grpc(hit_setup: SetupFixture) {
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": {}, "corp": {}, "canary": {} },
+        "network": {
+            "corp": { "proxy": "http://proxy.corp:3128" },
+            "canary": { "resolve": ["api.example.test:443:127.0.0.1"] }
+        },
+        "commands": {
+            "prices": { "method": "GET", "url": "ws://api.example.test/prices" },
+            "hello": {
+                "method": "POST",
+                "url": "https://api.example.test",
+                "grpc": { "service": "hello.Greeter", "method": "SayHello" }
+            }
+        }
+    }));
+
+    for (args, error) in [
+        (
+            ["run", "--env", "corp", "prices"].as_slice(),
+            "proxy is not supported for websocket commands\n",
+        ),
+        (
+            ["run", "--env", "canary", "hello"].as_slice(),
+            "resolve is not supported for gRPC commands\n",
+        ),
+        (
+            ["run", "--env", "dev", "--unix-socket", "api.sock", "prices"].as_slice(),
+            "unix_socket is not supported for websocket commands\n",
+        ),
+    ] {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(args);
+        cmd.assert().failure().stderr(error);
+    }
+}
+
+#[cfg(unix)]
+#[rstest]
+fn test_unix_socket(hit_setup: SetupFixture) {
+    let socket = hit_setup.temp_dir.path().join("docker.sock");
+    let server =
+        MockServer::start_unix(&socket, 200, &[("Content-Type", "application/json")], b"[]");
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "local": { "DOCKER": server.url }, "socket": {} },
+        "network": { "socket": { "unix_socket": "docker.sock" } },
+        "commands": {
+            "containers": { "method": "GET", "url": "{{DOCKER}}:/containers/json" },
+            "images": { "method": "GET", "url": "http://localhost/images/json" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.env("NO_COLOR", "1");
+    cmd.args(["run", "--env", "local", "containers"]);
+    cmd.assert().success().stdout("[]\n");
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "socket", "images"]);
+    cmd.assert().success();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "local", "--unix-socket"]);
+    cmd.arg(&socket);
+    cmd.arg("images");
+    cmd.assert().success();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].starts_with("GET /containers/json HTTP/1.1\r\n"));
+    assert!(requests[1].starts_with("GET /images/json HTTP/1.1\r\n"));
+    assert!(requests[2].starts_with("GET /images/json HTTP/1.1\r\n"));
+}
+
+#[rstest]
+fn test_repeated_headers(hit_setup: SetupFixture) {
+    let server = MockServer::start(
+        200,
+        &[
+            ("Content-Type", "application/json"),
+            ("Set-Cookie", "a=1"),
+            ("Set-Cookie", "b=2"),
+            ("X-Note", "café"),
+        ],
+        b"{}",
+    );
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "items": {
+                "method": "GET",
+                "url": "{{API_URL}}/items",
+                "headers": {
+                    "User-Agent": "legacy-client/1.0",
+                    "Accept": ["application/json", "text/plain"]
+                }
+            }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "--dry-run", "items"]);
+    cmd.assert().success().stdout(format!(
+        "GET {}/items\n\
+         Accept: application/json\n\
+         Accept: text/plain\n\
+         User-Agent: legacy-client/1.0\n",
+        server.url
+    ));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "items"]);
+    cmd.assert().success();
+
+    let request = &server.requests()[0];
+    assert!(request.contains("\r\nuser-agent: legacy-client/1.0\r\n"));
+    assert!(!request.contains("hit-cli"));
+    assert!(request.contains("\r\naccept: application/json\r\naccept: text/plain\r\n"));
+
+    let app_config: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap(),
+    )
+    .unwrap();
+    let prev_request = app_config["prev_request"].as_object().unwrap();
+    let headers = &prev_request.values().next().unwrap()["headers"];
+    assert_eq!(headers["set-cookie"], serde_json::json!(["a=1", "b=2"]));
+    assert_eq!(headers["x-note"], "café");
+}
+
+#[rstest]
+fn test_non_utf8_response_headers(hit_setup: SetupFixture) {
+    let server = MockServer::start_raw(
+        b"HTTP/1.1 200 OK\r\nConnection: close\r\n\
+          Content-Disposition: attachment; filename=\"caf\xe9.txt\"\r\n\
+          X-Path: C:\\xe9\r\n\
+          Content-Length: 2\r\n\r\nok"
+            .to_vec(),
+    );
+    hit_setup.write_config(serde_json::json!({
+        "envs": { "dev": { "API_URL": server.url } },
+        "commands": {
+            "download": { "method": "GET", "url": "{{API_URL}}/download" }
+        }
+    }));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "download"]);
+    cmd.assert().success();
+
+    let app_config: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap(),
+    )
+    .unwrap();
+    let prev_request = app_config["prev_request"].as_object().unwrap();
+    let headers = &prev_request.values().next().unwrap()["headers"];
+    assert_eq!(
+        headers["content-disposition"],
+        "attachment; filename=\"caf\\xe9.txt\""
+    );
+    assert_eq!(headers["x-path"], "C:\\\\xe9");
+}
@@ -0,0 +1,163 @@
+mod fixtures;
+use assert_cmd::prelude::*;
+use fixtures::{get_hit_command_for_setup, hit_setup, MockServer, SetupFixture};
+use predicates::prelude::*;
+use rstest::*;
+use std::fs;
+
+#[rstest]
+fn test_concurrent_updates_are_not_lost(hit_setup: SetupFixture) {
+    let children: Vec<_> = (0..10)
+        .map(|i| {
+            let mut cmd = get_hit_command_for_setup(&hit_setup);
+            cmd.args(["ephenv", "set", &format!("KEY_{}", i), "value"]);
+            cmd.spawn().unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["ephenv", "list"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    for i in 0..10 {
+        assert!(output.contains(&format!("KEY_{}", i)));
+    }
+}
+
+#[rstest]
+fn test_corrupt_state_is_recovered(hit_setup: SetupFixture) {
+    let state_path = hit_setup.temp_dir.path().join("config.json");
+    fs::write(&state_path, "{\"envs\": {").unwrap();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["ephenv", "set", "KEY", "value"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("warning: could not read"));
+
+    assert_eq!(
+        fs::read_to_string(hit_setup.temp_dir.path().join("config.json.corrupt")).unwrap(),
+        "{\"envs\": {"
+    );
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["ephenv", "get", "KEY"]);
+    cmd.assert().success().stdout("value\n").stderr("");
+}
+
+fn ping_config(url: &str) -> serde_json::Value {
+    serde_json::json!({
+        "envs": { "dev": { "API_URL": url } },
+        "commands": {
+            "ping": { "method": "GET", "url": "{{API_URL}}/ping" }
+        }
+    })
+}
+
+fn list_response_bodies(setup: &SetupFixture) -> Vec<fs::DirEntry> {
+    fs::read_dir(setup.temp_dir.path().join("responses"))
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect()
+}
+
+#[rstest]
+fn test_response_bodies_are_stored_separately(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"response-body");
+    hit_setup.write_config(ping_config(&server.url));
+
+    for _ in 0..2 {
+        let mut cmd = get_hit_command_for_setup(&hit_setup);
+        cmd.args(["run", "--env", "dev", "ping"]);
+        cmd.assert().success();
+    }
+
+    let state = fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(!state.contains("response-body"));
+
+    let bodies = list_response_bodies(&hit_setup);
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(
+        fs::read_to_string(bodies[0].path()).unwrap(),
+        "response-body"
+    );
+}
+
+#[rstest]
+fn test_large_response_bodies_are_not_stored(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], &vec![b'a'; 3 * 1024 * 1024 / 2]);
+    hit_setup.write_config(ping_config(&server.url));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "ping"]);
+    cmd.assert().success();
+
+    assert!(!hit_setup.temp_dir.path().join("responses").exists());
+    let state = fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(state.contains("\"reason\": \"too_large\""));
+    assert!(state.contains("\"size\": 1572864"));
+}
+
+#[rstest]
+fn test_least_recently_used_projects_are_evicted(hit_setup: SetupFixture) {
+    let last_used: serde_json::Map<String, serde_json::Value> = (0..100)
+        .map(|i| (format!("/projects/{}", i), serde_json::json!(i + 1)))
+        .collect();
+    fs::write(
+        hit_setup.temp_dir.path().join("config.json"),
+        serde_json::json!({ "last_used": last_used }).to_string(),
+    )
+    .unwrap();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["env", "use", "dev"]);
+    cmd.assert().success();
+
+    let state = fs::read_to_string(hit_setup.temp_dir.path().join("config.json")).unwrap();
+    assert!(!state.contains("\"/projects/0\""));
+    assert!(state.contains("\"/projects/1\""));
+    assert!(state.contains("\"/projects/99\""));
+}
+
+#[rstest]
+fn test_state_gc(hit_setup: SetupFixture) {
+    let server = MockServer::start(200, &[], b"response-body");
+    hit_setup.write_config(ping_config(&server.url));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["run", "--env", "dev", "ping"]);
+    cmd.assert().success();
+
+    let state_path = hit_setup.temp_dir.path().join("config.json");
+    let mut state: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&state_path).unwrap()).unwrap();
+    state["envs"]["/no/such/project"] = serde_json::json!("dev");
+    fs::write(&state_path, state.to_string()).unwrap();
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["state", "show"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("/no/such/project"))
+        .stdout(predicate::str::contains("(current)"))
+        .stdout(predicate::str::contains("  responses: dev (13 B)"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["state", "gc"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "removed /no/such/project (no longer a hit project)",
+        ))
+        .stdout(predicate::str::contains("removed 1 project(s)"));
+
+    let mut cmd = get_hit_command_for_setup(&hit_setup);
+    cmd.args(["state", "gc", "--older-than", "0s"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("freed 13 B of response bodies"));
+    assert!(list_response_bodies(&hit_setup).is_empty());
+}